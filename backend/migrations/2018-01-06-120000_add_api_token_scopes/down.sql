@@ -0,0 +1,10 @@
+CREATE TABLE api_tokens_old(
+  token TEXT PRIMARY KEY,
+  kind TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  expires_at BIGINT,
+  created_by TEXT references users(id) ON DELETE CASCADE
+);
+INSERT INTO api_tokens_old SELECT token, kind, created_at, expires_at, created_by FROM api_tokens;
+DROP TABLE api_tokens;
+ALTER TABLE api_tokens_old RENAME TO api_tokens;
@@ -0,0 +1,2 @@
+ALTER TABLE api_tokens ADD COLUMN name TEXT;
+ALTER TABLE api_tokens ADD COLUMN scopes TEXT;
@@ -0,0 +1,49 @@
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TABLE languages_old(
+  id TEXT PRIMARY KEY,
+  code TEXT NOT NULL UNIQUE,
+  name TEXT NOT NULL,
+  parent_id TEXT REFERENCES languages_old (id) ON DELETE SET NULL,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL
+);
+INSERT INTO languages_old (id, code, name, parent_id, created_at, created_by)
+  SELECT id, code, name, parent_id, created_at, created_by FROM languages
+  WHERE project_id = 'default';
+
+CREATE TABLE keys_old(
+  id TEXT PRIMARY KEY,
+  key TEXT NOT NULL UNIQUE,
+  description TEXT,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (username) ON DELETE SET NULL
+);
+INSERT INTO keys_old (id, key, description, created_at, created_by)
+  SELECT id, key, description, created_at, created_by FROM keys
+  WHERE project_id = 'default';
+
+CREATE TABLE translations_old(
+  id TEXT PRIMARY KEY,
+  language_id TEXT NOT NULL REFERENCES languages_old (id) ON DELETE CASCADE,
+  key_id TEXT NOT NULL REFERENCES keys_old (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  UNIQUE (language_id, key_id)
+);
+INSERT INTO translations_old (id, language_id, key_id, version, value, created_at, updated_at, created_by)
+  SELECT id, language_id, key_id, version, value, created_at, updated_at, created_by FROM translations
+  WHERE key_id IN (SELECT id FROM keys_old);
+
+DROP TABLE translations;
+DROP TABLE keys;
+DROP TABLE languages;
+DROP TABLE project_members;
+DROP TABLE projects;
+
+ALTER TABLE languages_old RENAME TO languages;
+ALTER TABLE keys_old RENAME TO keys;
+ALTER TABLE translations_old RENAME TO translations;
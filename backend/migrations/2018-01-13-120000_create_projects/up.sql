@@ -0,0 +1,70 @@
+-- Languages and keys are scoped per project, which requires rebuilding the
+-- tables to replace the global unique constraints.
+-- Existing data is moved into a default project.
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TABLE projects(
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE TABLE project_members(
+  project_id TEXT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+  user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  created_at BIGINT NOT NULL,
+  PRIMARY KEY (project_id, user_id)
+);
+
+INSERT INTO projects (id, name, created_at) VALUES ('default', 'Default', strftime('%s', 'now'));
+INSERT INTO project_members (project_id, user_id, created_at)
+  SELECT 'default', id, strftime('%s', 'now') FROM users;
+
+CREATE TABLE languages_new(
+  id TEXT PRIMARY KEY,
+  project_id TEXT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+  code TEXT NOT NULL,
+  name TEXT NOT NULL,
+  parent_id TEXT REFERENCES languages_new (id) ON DELETE SET NULL,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  UNIQUE (project_id, code)
+);
+INSERT INTO languages_new (id, project_id, code, name, parent_id, created_at, created_by)
+  SELECT id, 'default', code, name, parent_id, created_at, created_by FROM languages;
+
+CREATE TABLE keys_new(
+  id TEXT PRIMARY KEY,
+  project_id TEXT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+  key TEXT NOT NULL,
+  description TEXT,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (username) ON DELETE SET NULL,
+  UNIQUE (project_id, key)
+);
+INSERT INTO keys_new (id, project_id, key, description, created_at, created_by)
+  SELECT id, 'default', key, description, created_at, created_by FROM keys;
+
+CREATE TABLE translations_new(
+  id TEXT PRIMARY KEY,
+  language_id TEXT NOT NULL REFERENCES languages_new (id) ON DELETE CASCADE,
+  key_id TEXT NOT NULL REFERENCES keys_new (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  UNIQUE (language_id, key_id)
+);
+INSERT INTO translations_new (id, language_id, key_id, version, value, created_at, updated_at, created_by)
+  SELECT id, language_id, key_id, version, value, created_at, updated_at, created_by FROM translations;
+
+DROP TABLE translations;
+DROP TABLE keys;
+DROP TABLE languages;
+
+-- Renaming also updates the foreign key references of the other new tables.
+ALTER TABLE languages_new RENAME TO languages;
+ALTER TABLE keys_new RENAME TO keys;
+ALTER TABLE translations_new RENAME TO translations;
@@ -0,0 +1,7 @@
+-- Per project configuration.
+-- Kept out of the projects table so columns can be added and removed without
+-- rebuilding the tables that reference projects.
+CREATE TABLE project_settings(
+  project_id TEXT PRIMARY KEY REFERENCES projects (id) ON DELETE CASCADE,
+  default_language_id TEXT REFERENCES languages (id) ON DELETE SET NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE project_settings_old(
+  project_id TEXT PRIMARY KEY REFERENCES projects (id) ON DELETE CASCADE,
+  default_language_id TEXT REFERENCES languages (id) ON DELETE SET NULL
+);
+INSERT INTO project_settings_old SELECT project_id, default_language_id FROM project_settings;
+DROP TABLE project_settings;
+ALTER TABLE project_settings_old RENAME TO project_settings;
@@ -0,0 +1,3 @@
+-- How placeholders are written in the values of a project:
+-- icu for {name}, printf for %s and %1$d, or mustache for {{name}}.
+ALTER TABLE project_settings ADD COLUMN placeholder_syntax TEXT NOT NULL DEFAULT 'icu';
@@ -0,0 +1,37 @@
+-- Dropping the column requires rebuilding the keys table, and with it the
+-- translations that reference it.
+PRAGMA defer_foreign_keys = ON;
+
+DROP TABLE plural_forms;
+
+CREATE TABLE keys_old(
+  id TEXT PRIMARY KEY,
+  project_id TEXT NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+  key TEXT NOT NULL,
+  description TEXT,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (username) ON DELETE SET NULL,
+  UNIQUE (project_id, key)
+);
+INSERT INTO keys_old (id, project_id, key, description, created_at, created_by)
+  SELECT id, project_id, key, description, created_at, created_by FROM keys;
+
+CREATE TABLE translations_old(
+  id TEXT PRIMARY KEY,
+  language_id TEXT NOT NULL REFERENCES languages (id) ON DELETE CASCADE,
+  key_id TEXT NOT NULL REFERENCES keys_old (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  UNIQUE (language_id, key_id)
+);
+INSERT INTO translations_old (id, language_id, key_id, version, value, created_at, updated_at, created_by)
+  SELECT id, language_id, key_id, version, value, created_at, updated_at, created_by FROM translations;
+
+DROP TABLE translations;
+DROP TABLE keys;
+
+ALTER TABLE keys_old RENAME TO keys;
+ALTER TABLE translations_old RENAME TO translations;
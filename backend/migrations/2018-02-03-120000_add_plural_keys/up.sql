@@ -0,0 +1,10 @@
+ALTER TABLE keys ADD COLUMN plural BOOLEAN NOT NULL DEFAULT 0;
+
+-- The values of plural translations by CLDR category.
+-- The value of the translation itself holds the other form.
+CREATE TABLE plural_forms(
+  translation_id TEXT NOT NULL REFERENCES translations (id) ON DELETE CASCADE,
+  category TEXT NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (translation_id, category)
+);
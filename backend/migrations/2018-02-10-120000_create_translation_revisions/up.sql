@@ -0,0 +1,16 @@
+-- Every value a translation had, by version.
+-- Plural values are stored as a json object by category.
+CREATE TABLE translation_revisions(
+  translation_id TEXT NOT NULL REFERENCES translations (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  plural_values TEXT,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  PRIMARY KEY (translation_id, version)
+);
+
+-- Only the current values are known for existing translations, and only the
+-- author of the first version.
+INSERT INTO translation_revisions (translation_id, version, value, created_at, created_by)
+  SELECT id, version, value, updated_at, CASE WHEN version = 1 THEN created_by END FROM translations;
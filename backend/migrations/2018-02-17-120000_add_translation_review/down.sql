@@ -0,0 +1,46 @@
+-- Dropping the columns requires rebuilding the translations table, and with
+-- it the tables that reference it.
+PRAGMA defer_foreign_keys = ON;
+
+CREATE TABLE translations_old(
+  id TEXT PRIMARY KEY,
+  language_id TEXT NOT NULL REFERENCES languages (id) ON DELETE CASCADE,
+  key_id TEXT NOT NULL REFERENCES keys (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  UNIQUE (language_id, key_id)
+);
+INSERT INTO translations_old (id, language_id, key_id, version, value, created_at, updated_at, created_by)
+  SELECT id, language_id, key_id, version, value, created_at, updated_at, created_by FROM translations;
+
+CREATE TABLE plural_forms_old(
+  translation_id TEXT NOT NULL REFERENCES translations_old (id) ON DELETE CASCADE,
+  category TEXT NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (translation_id, category)
+);
+INSERT INTO plural_forms_old (translation_id, category, value)
+  SELECT translation_id, category, value FROM plural_forms;
+
+CREATE TABLE translation_revisions_old(
+  translation_id TEXT NOT NULL REFERENCES translations_old (id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  value TEXT NOT NULL,
+  plural_values TEXT,
+  created_at BIGINT NOT NULL,
+  created_by TEXT REFERENCES users (id) ON DELETE SET NULL,
+  PRIMARY KEY (translation_id, version)
+);
+INSERT INTO translation_revisions_old (translation_id, version, value, plural_values, created_at, created_by)
+  SELECT translation_id, version, value, plural_values, created_at, created_by FROM translation_revisions;
+
+DROP TABLE translation_revisions;
+DROP TABLE plural_forms;
+DROP TABLE translations;
+
+ALTER TABLE translations_old RENAME TO translations;
+ALTER TABLE plural_forms_old RENAME TO plural_forms;
+ALTER TABLE translation_revisions_old RENAME TO translation_revisions;
@@ -0,0 +1,6 @@
+-- Existing translations are live already, so they count as approved.
+ALTER TABLE translations ADD COLUMN status TEXT NOT NULL DEFAULT 'approved';
+-- The reason given for rejections.
+ALTER TABLE translations ADD COLUMN review_comment TEXT;
+ALTER TABLE translations ADD COLUMN reviewed_at BIGINT;
+ALTER TABLE translations ADD COLUMN reviewed_by TEXT REFERENCES users (id) ON DELETE SET NULL;
@@ -0,0 +1,17 @@
+-- The language translations are made from, the default language if null.
+ALTER TABLE project_settings ADD COLUMN source_language_id TEXT REFERENCES languages (id) ON DELETE SET NULL;
+
+-- The version of the source translation a translation was made against.
+ALTER TABLE translations ADD COLUMN source_version INT;
+-- Set when the source translation changed after the translation was made.
+ALTER TABLE translations ADD COLUMN outdated BOOLEAN NOT NULL DEFAULT 0;
+
+-- Existing translations are assumed to match the current source values.
+UPDATE translations SET source_version = (
+  SELECT source.version FROM translations source
+    JOIN languages ON languages.id = translations.language_id
+    JOIN project_settings ON project_settings.project_id = languages.project_id
+  WHERE source.key_id = translations.key_id
+    AND source.language_id = project_settings.default_language_id
+    AND source.id != translations.id
+);
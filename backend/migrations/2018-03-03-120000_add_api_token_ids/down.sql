@@ -0,0 +1,15 @@
+DROP INDEX api_tokens_id;
+
+CREATE TABLE api_tokens_old(
+  token TEXT PRIMARY KEY,
+  kind TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  expires_at BIGINT,
+  created_by TEXT references users(id) ON DELETE CASCADE,
+  name TEXT,
+  scopes TEXT
+);
+INSERT INTO api_tokens_old (token, kind, created_at, expires_at, created_by, name, scopes)
+  SELECT token, kind, created_at, expires_at, created_by, name, scopes FROM api_tokens;
+DROP TABLE api_tokens;
+ALTER TABLE api_tokens_old RENAME TO api_tokens;
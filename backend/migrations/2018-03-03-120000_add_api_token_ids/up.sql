@@ -0,0 +1,4 @@
+-- Identifies tokens in listings and revocations without revealing them.
+ALTER TABLE api_tokens ADD COLUMN id TEXT NOT NULL DEFAULT '';
+UPDATE api_tokens SET id = lower(hex(randomblob(16)));
+CREATE UNIQUE INDEX api_tokens_id ON api_tokens (id);
@@ -0,0 +1,5 @@
+-- The last version of a translation that was approved, exported instead of
+-- the current value while that awaits review.
+ALTER TABLE translations ADD COLUMN approved_version INT;
+
+UPDATE translations SET approved_version = version WHERE status = 'approved';
@@ -1,8 +1,12 @@
-use juniper::{FieldResult as Res};
+use std::collections::HashMap;
+
+use juniper::{FieldResult as Res, FieldError, Value};
 use uuid::Uuid;
 
 pub use super::{Ctx};
+use ::error::{Error, ErrorKind};
 use ::db::schema::*;
+use ::repo::{ImportFormat, ImportMode, ImportReport};
 
 pub struct Mutation;
 
@@ -15,6 +19,105 @@ graphql_object!(Mutation: Ctx |&self| {
         Ok(token)
     }
 
+    field logout(&executor) -> Res<bool> {
+        let ctx = executor.context();
+        match ctx.token() {
+            Some(token) => {
+                ctx.repo().logout(token)?;
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    field create_api_token(&executor, name: String, scopes: Vec<String>, expires_at: Option<f64>) -> Res<ApiToken> {
+        let ctx = executor.context();
+        let token = ctx.repo()
+                       .create_api_token(name, scopes, expires_at.map(|x| x as i64), ctx.user())?;
+        Ok(token)
+    }
+
+    field revoke_api_token(&executor, id: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().revoke_api_token(&id, ctx.user())?;
+        Ok(true)
+    }
+
+    field create_user(&executor, username: String, role: String, password: String) -> Res<User> {
+        let ctx = executor.context();
+        let user = ctx.repo()
+                      .create_user(username, &role, password, ctx.user())?;
+        Ok(user)
+    }
+
+    field update_user(&executor, id: String, role: Option<String>, password: Option<String>) -> Res<User> {
+        let ctx = executor.context();
+        let user = ctx.repo()
+                      .update_user(&id, role, password, ctx.user())?;
+        Ok(user)
+    }
+
+    field delete_user(&executor, id: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().delete_user(&id, ctx.user())?;
+        Ok(true)
+    }
+
+    field change_password(&executor, old_password: String, new_password: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().change_password(&old_password, &new_password, ctx.user())?;
+        Ok(true)
+    }
+
+    field create_project(&executor, project: NewProject) -> Res<Project> {
+        let ctx = executor.context();
+        let project = ctx.repo()
+                         .create_project(project, ctx.user())?;
+        Ok(project)
+    }
+
+    field delete_project(&executor, id: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().delete_project(&id, ctx.user())?;
+        Ok(true)
+    }
+
+    field add_project_member(&executor, project_id: String, user_id: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().add_project_member(&project_id, &user_id, ctx.user())?;
+        Ok(true)
+    }
+
+    field remove_project_member(&executor, project_id: String, user_id: String) -> Res<bool> {
+        let ctx = executor.context();
+        ctx.repo().remove_project_member(&project_id, &user_id, ctx.user())?;
+        Ok(true)
+    }
+
+    field set_default_language(&executor, project_id: String, language_id: Option<String>) -> Res<Project> {
+        let ctx = executor.context();
+        let mut repo = ctx.repo();
+        repo.set_default_language(&project_id, language_id.as_ref().map(|x| x.as_str()), ctx.user())?;
+        let project = repo.project(&project_id, ctx.user())?.ok_or("Unknown project")?;
+        Ok(project)
+    }
+
+    field set_source_language(&executor, project_id: String, language_id: Option<String>) -> Res<Project> {
+        let ctx = executor.context();
+        let mut repo = ctx.repo();
+        repo.set_source_language(&project_id, language_id.as_ref().map(|x| x.as_str()), ctx.user())?;
+        let project = repo.project(&project_id, ctx.user())?.ok_or("Unknown project")?;
+        Ok(project)
+    }
+
+    field set_placeholder_syntax(&executor, project_id: String, syntax: String) -> Res<Project> {
+        let ctx = executor.context();
+        let mut repo = ctx.repo();
+        repo.set_placeholder_syntax(&project_id, &syntax, ctx.user())?;
+        let project = repo.project(&project_id, ctx.user())?.ok_or("Unknown project")?;
+        Ok(project)
+    }
+
     field create_language(&executor, lang: NewLanguage) -> Res<Language> {
         let ctx = executor.context();
         let lang = ctx.repo()
@@ -42,6 +145,12 @@ graphql_object!(Mutation: Ctx |&self| {
         Ok(key)
     }
 
+    field set_key_plural(&executor, id: String, plural: bool) -> Res<Key> {
+        let ctx = executor.context();
+        let key = ctx.repo().set_key_plural(&id, plural, ctx.user())?;
+        Ok(key)
+    }
+
     field delete_key(&executor, key: String) -> Res<bool> {
         let ctx = executor.context();
             ctx.repo()
@@ -52,14 +161,54 @@ graphql_object!(Mutation: Ctx |&self| {
     field translate(&executor, translation: NewTranslation) -> Res<Translation> {
         let ctx = executor.context();
         let translation = ctx.repo()
-                       .translate(translation, ctx.user())?;
+                       .translate(translation, ctx.user())
+                       .map_err(conflict_error)?;
         Ok(translation)
     }
 
+    field import_translations(&executor, language_id: String, format: String, content: String, mode: String)
+        -> Res<ImportReport>
+    {
+        let ctx = executor.context();
+        let format = ImportFormat::from_str(&format)
+                                  .ok_or("Unsupported import format")?;
+        let mode = ImportMode::from_str(&mode)
+                              .ok_or("Invalid import mode: must be add-missing, overwrite or dry-run")?;
+        let report = ctx.repo()
+                        .import_translations(&language_id, format, &content, mode, ctx.user())?;
+        Ok(report)
+    }
+
+    // Same as translate, which creates or updates translations.
     field update_translation(&executor, translation: NewTranslation) -> Res<Translation> {
         let ctx = executor.context();
         let translation = ctx.repo()
-                       .update_translation(translation, ctx.user())?;
+                       .translate(translation, ctx.user())
+                       .map_err(conflict_error)?;
+        Ok(translation)
+    }
+
+    field revert_translation(&executor, id: String, version: i32) -> Res<Translation> {
+        let ctx = executor.context();
+        let translation = ctx.repo()
+                       .revert_translation(&id, version, ctx.user())
+                       .map_err(conflict_error)?;
+        Ok(translation)
+    }
+
+    field approve_translation(&executor, id: String) -> Res<Translation> {
+        let ctx = executor.context();
+        let translation = ctx.repo()
+                       .approve_translation(&id, ctx.user())
+                       .map_err(conflict_error)?;
+        Ok(translation)
+    }
+
+    field reject_translation(&executor, id: String, comment: String) -> Res<Translation> {
+        let ctx = executor.context();
+        let translation = ctx.repo()
+                       .reject_translation(&id, comment, ctx.user())
+                       .map_err(conflict_error)?;
         Ok(translation)
     }
 
@@ -71,3 +220,27 @@ graphql_object!(Mutation: Ctx |&self| {
     }
 
 });
+
+/// Conflicting updates carry the current value and its author, so clients
+/// can offer a merge. The current value of plural translations is an object
+/// with the value of each category.
+fn conflict_error(e: Error) -> FieldError {
+    let data = match *e.kind() {
+        ErrorKind::TranslationConflict(ref current, ref forms, ref author) => {
+            let value = match *current {
+                Some(_) if !forms.is_empty() => {
+                    Value::object(forms.iter().map(|f| (f.category.as_str(), Value::string(&f.value))).collect())
+                },
+                Some(ref t) => Value::string(&t.value),
+                None => Value::null(),
+            };
+            let mut data = HashMap::new();
+            data.insert("currentVersion", current.as_ref().map(|t| Value::int(t.version)).unwrap_or(Value::null()));
+            data.insert("currentValue", value);
+            data.insert("author", author.as_ref().map(|a| Value::string(a)).unwrap_or(Value::null()));
+            Value::object(data)
+        },
+        _ => Value::null(),
+    };
+    FieldError::new(e, data)
+}
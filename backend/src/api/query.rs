@@ -8,20 +8,53 @@ pub struct Query;
 
 graphql_object!(Query: Ctx |&self| {
 
-    field languages(&executor) -> Res<Vec<Language>> {
+    field projects(&executor) -> Res<Vec<Project>> {
         let ctx = executor.context();
+        let projects = ctx.repo()
+                          .projects(ctx.user())?;
+        Ok(projects)
+    }
+
+    field project(&executor, id: String) -> Res<Option<Project>> {
+        let ctx = executor.context();
+        let project = ctx.repo().project(&id, ctx.user())?;
+        Ok(project)
+    }
+
+    field languages(&executor, project_id: Option<String>) -> Res<Vec<Language>> {
+        let ctx = executor.context();
+        let project_id = project_id.unwrap_or(DEFAULT_PROJECT.to_string());
         let langs = ctx.repo()
-                       .languages(ctx.user())?;
+                       .languages(&project_id, ctx.user())?;
         Ok(langs)
     }
 
-    field language(&executor, id: Uuid) -> Res<Option<Language>> {
-        let _id = id.to_string();
+    field language(&executor, id: Option<Uuid>, code: Option<String>, project_id: Option<String>) -> Res<Option<Language>> {
         let ctx = executor.context();
-        let lang = ctx.repo().language(&_id, ctx.user())?;
+        let lang = match (id, code) {
+            (Some(id), None) => ctx.repo().language(&id.to_string(), ctx.user())?,
+            (None, Some(code)) => {
+                let project_id = project_id.unwrap_or(DEFAULT_PROJECT.to_string());
+                ctx.repo().language_by_code(&project_id, &code, ctx.user())?
+            },
+            _ => {
+                return Err("Specify either id or code".into());
+            },
+        };
         Ok(lang)
     }
 
+    field me(&executor) -> Option<User> {
+        executor.context().user().cloned()
+    }
+
+    field api_tokens(&executor) -> Res<Vec<ApiTokenInfo>> {
+        let ctx = executor.context();
+        let tokens = ctx.repo()
+                        .api_tokens(ctx.user())?;
+        Ok(tokens)
+    }
+
     field users(&executor) -> Res<Vec<User>> {
         let ctx = executor.context();
         let langs = ctx.repo()
@@ -29,20 +62,40 @@ graphql_object!(Query: Ctx |&self| {
         Ok(langs)
     }
 
-      field keys(&executor) -> Res<Vec<Key>> {
+      field keys(&executor, project_id: Option<String>) -> Res<Vec<Key>> {
         let ctx = executor.context();
+        let project_id = project_id.unwrap_or(DEFAULT_PROJECT.to_string());
         let keys = ctx.repo()
-                       .keys(ctx.user())?;
+                       .keys(&project_id, ctx.user())?;
         Ok(keys)
     }
 
-    field key(&executor, key: String) -> Res<Option<Key>> {
+    field key(&executor, key: String, project_id: Option<String>) -> Res<Option<Key>> {
         let ctx = executor.context();
+        let project_id = project_id.unwrap_or(DEFAULT_PROJECT.to_string());
         let key = ctx.repo()
-                       .key_by_key(&key, ctx.user())?;
+                       .key_by_key(&project_id, &key, ctx.user())?;
         Ok(key)
     }
 
+    field validate_message(message: String) -> ::message_format::Validation {
+        ::message_format::validate(&message)
+    }
+
+    field lint_translations(&executor, language_id: String) -> Res<Vec<::repo::TranslationLint>> {
+        let ctx = executor.context();
+        let lints = ctx.repo()
+                       .lint_translations(&language_id, ctx.user())?;
+        Ok(lints)
+    }
+
+    field stale_translations(&executor, language_id: String) -> Res<Vec<Translation>> {
+        let ctx = executor.context();
+        let translations = ctx.repo()
+                              .stale_translations(&language_id, ctx.user())?;
+        Ok(translations)
+    }
+
     field translations(&executor, key: String) -> Res<Vec<Translation>> {
         let ctx = executor.context();
         let langs = ctx.repo()
@@ -50,6 +103,20 @@ graphql_object!(Query: Ctx |&self| {
         Ok(langs)
     }
 
+    field translation_history(&executor, id: String) -> Res<Vec<TranslationRevision>> {
+        let ctx = executor.context();
+        let revisions = ctx.repo()
+                           .translation_history(&id, ctx.user())?;
+        Ok(revisions)
+    }
+
+    field translation_diff(&executor, id: String, from_version: i32, to_version: i32) -> Res<Vec<::diff::ValueDiff>> {
+        let ctx = executor.context();
+        let changes = ctx.repo()
+                         .translation_diff(&id, from_version, to_version, ctx.user())?;
+        Ok(changes)
+    }
+
     field id() -> String {
         "a".to_string()
     }
@@ -1,13 +1,85 @@
 use juniper::{FieldResult};
 
 use ::db::schema::*;
+use ::repo::{ImportReport, ImportError, TranslationLint};
+use ::lint::LintIssue;
+use ::diff::{Change, ValueDiff};
+use ::message_format::{ParseError, Validation};
 use super::Ctx;
 
+graphql_object!(Project: Ctx |&self| {
+    field id() -> String {
+        self.id.clone()
+    }
+
+    field name() -> String {
+        self.name.clone()
+    }
+
+    field created_at() -> f64 {
+        self.created_at as f64
+    }
+
+    field created_by() -> Option<String> {
+        self.created_by.clone()
+    }
+
+    field members(&executor) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let users = ctx.repo().project_members(&self.id, ctx.user())?;
+        Ok(users)
+    }
+
+    field languages(&executor) -> FieldResult<Vec<Language>> {
+        let ctx = executor.context();
+        let langs = ctx.repo().languages(&self.id, ctx.user())?;
+        Ok(langs)
+    }
+
+    field keys(&executor) -> FieldResult<Vec<Key>> {
+        let ctx = executor.context();
+        let keys = ctx.repo().keys(&self.id, ctx.user())?;
+        Ok(keys)
+    }
+
+    field default_language(&executor) -> FieldResult<Option<Language>> {
+        let ctx = executor.context();
+        let mut repo = ctx.repo();
+        let settings = repo.project_settings(&self.id, ctx.user())?;
+        let lang = match settings.default_language_id {
+            Some(id) => repo.language(&id, ctx.user())?,
+            None => None,
+        };
+        Ok(lang)
+    }
+
+    field source_language(&executor) -> FieldResult<Option<Language>> {
+        let ctx = executor.context();
+        let mut repo = ctx.repo();
+        let settings = repo.project_settings(&self.id, ctx.user())?;
+        let lang = match settings.source_language_id {
+            Some(id) => repo.language(&id, ctx.user())?,
+            None => None,
+        };
+        Ok(lang)
+    }
+
+    field placeholder_syntax(&executor) -> FieldResult<String> {
+        let ctx = executor.context();
+        let settings = ctx.repo().project_settings(&self.id, ctx.user())?;
+        Ok(settings.placeholder_syntax)
+    }
+});
+
 graphql_object!(Language: Ctx |&self| {
     field id() -> String {
         self.id.clone()
     }
 
+    field project_id() -> String {
+        self.project_id.clone()
+    }
+
     field code() -> &str {
         &self.code
     }
@@ -20,6 +92,10 @@ graphql_object!(Language: Ctx |&self| {
         self.parent_id.clone()
     }
 
+    field plural_categories() -> Vec<String> {
+        ::plurals::rule(&self.code).categories.iter().map(|c| c.to_string()).collect()
+    }
+
     field created_at() -> f64 {
         self.created_at as f64
     }
@@ -51,6 +127,18 @@ graphql_object!(Translation: Ctx |&self| {
         self.value.clone()
     }
 
+    field plural_values(&executor) -> FieldResult<Vec<PluralForm>> {
+        let ctx = executor.context();
+        let forms = ctx.repo().plural_forms(&self.id, ctx.user())?;
+        Ok(forms)
+    }
+
+    field issues(&executor) -> FieldResult<Vec<LintIssue>> {
+        let ctx = executor.context();
+        let issues = ctx.repo().translation_issues(&self.id, ctx.user())?;
+        Ok(issues)
+    }
+
     field created_at() -> f64 {
         self.created_at as f64
     }
@@ -63,6 +151,91 @@ graphql_object!(Translation: Ctx |&self| {
         self.created_by.clone()
     }
 
+    field status() -> String {
+        self.status.clone()
+    }
+
+    field review_comment() -> Option<String> {
+        self.review_comment.clone()
+    }
+
+    field reviewed_at() -> Option<f64> {
+        self.reviewed_at.map(|x| x as f64)
+    }
+
+    field reviewed_by() -> Option<String> {
+        self.reviewed_by.clone()
+    }
+
+    field source_version() -> Option<i32> {
+        self.source_version
+    }
+
+    field outdated() -> bool {
+        self.outdated
+    }
+
+    field approved_version() -> Option<i32> {
+        self.approved_version
+    }
+
+});
+
+graphql_object!(PluralForm: Ctx |&self| {
+    field category() -> String {
+        self.category.clone()
+    }
+
+    field value() -> String {
+        self.value.clone()
+    }
+});
+
+graphql_object!(TranslationRevision: Ctx |&self| {
+    field translation_id() -> String {
+        self.translation_id.clone()
+    }
+
+    field version() -> i32 {
+        self.version
+    }
+
+    field value() -> String {
+        self.value.clone()
+    }
+
+    field plural_values() -> FieldResult<Vec<PluralForm>> {
+        let forms = self.plural_forms()?;
+        Ok(forms)
+    }
+
+    field created_at() -> f64 {
+        self.created_at as f64
+    }
+
+    field created_by() -> Option<String> {
+        self.created_by.clone()
+    }
+});
+
+graphql_object!(Change: Ctx as "TranslationChange" |&self| {
+    field kind() -> &str {
+        self.kind.to_str()
+    }
+
+    field text() -> String {
+        self.text.clone()
+    }
+});
+
+graphql_object!(ValueDiff: Ctx as "TranslationDiff" |&self| {
+    field category() -> Option<String> {
+        self.category.clone()
+    }
+
+    field changes() -> Vec<Change> {
+        self.changes.clone()
+    }
 });
 
 graphql_object!(User: Ctx |&self| {
@@ -79,10 +252,6 @@ graphql_object!(User: Ctx |&self| {
         self.role.clone()
     }
 
-    field password_hash() -> String {
-        self.password_hash.clone()
-    }
-
     field created_at() -> f64 {
         self.created_at as f64
     }
@@ -94,6 +263,10 @@ graphql_object!(Key: Ctx |&self| {
         self.id.clone()
     }
 
+    field project_id() -> String {
+        self.project_id.clone()
+    }
+
     field key() -> String {
         self.key.clone()
     }
@@ -102,6 +275,10 @@ graphql_object!(Key: Ctx |&self| {
         self.description.clone()
     }
 
+    field plural() -> bool {
+        self.plural
+    }
+
     field created_at() -> f64 {
         self.created_at as f64
     }
@@ -117,11 +294,147 @@ graphql_object!(Key: Ctx |&self| {
     }
 });
 
+graphql_object!(ParseError: Ctx as "MessageError" |&self| {
+    field message() -> String {
+        self.message.clone()
+    }
+
+    field position() -> i32 {
+        self.position as i32
+    }
+
+    field line() -> i32 {
+        self.line as i32
+    }
+
+    field column() -> i32 {
+        self.column as i32
+    }
+});
+
+graphql_object!(Validation: Ctx as "MessageValidation" |&self| {
+    field valid() -> bool {
+        self.error.is_none()
+    }
+
+    field error() -> Option<ParseError> {
+        self.error.clone()
+    }
+
+    field arguments() -> Vec<String> {
+        self.arguments.clone()
+    }
+});
+
+graphql_object!(LintIssue: Ctx |&self| {
+    field kind() -> &str {
+        self.kind.to_str()
+    }
+
+    field expected() -> Option<String> {
+        self.expected.clone()
+    }
+
+    field found() -> Option<String> {
+        self.found.clone()
+    }
+
+    field message() -> String {
+        self.to_string()
+    }
+});
+
+graphql_object!(TranslationLint: Ctx |&self| {
+    field key() -> Key {
+        self.key.clone()
+    }
+
+    field translation() -> Translation {
+        self.translation.clone()
+    }
+
+    field source() -> String {
+        self.source.clone()
+    }
+
+    field issues() -> Vec<LintIssue> {
+        self.issues.clone()
+    }
+});
+
+graphql_object!(ImportError: Ctx |&self| {
+    field key() -> String {
+        self.key.clone()
+    }
+
+    field message() -> String {
+        self.message.clone()
+    }
+});
+
+graphql_object!(ImportReport: Ctx |&self| {
+    field dry_run() -> bool {
+        self.dry_run
+    }
+
+    field created_keys() -> Vec<String> {
+        self.created_keys.clone()
+    }
+
+    field added() -> Vec<String> {
+        self.added.clone()
+    }
+
+    field updated() -> Vec<String> {
+        self.updated.clone()
+    }
+
+    field skipped() -> Vec<String> {
+        self.skipped.clone()
+    }
+
+    field unknown_keys() -> Vec<String> {
+        self.unknown_keys.clone()
+    }
+
+    field conflicts() -> Vec<ImportError> {
+        self.conflicts.clone()
+    }
+
+    field errors() -> Vec<ImportError> {
+        self.errors.clone()
+    }
+
+    field warnings() -> Vec<ImportError> {
+        self.warnings.clone()
+    }
+});
+
 graphql_object!(ApiToken: Ctx |&self| {
+    field id() -> String {
+        self.id.clone()
+    }
+
     field token() -> String {
         self.token.clone()
     }
 
+    field kind() -> String {
+        self.kind.clone()
+    }
+
+    field name() -> Option<String> {
+        self.name.clone()
+    }
+
+    field scopes() -> Vec<String> {
+        self.scope_list()
+            .unwrap_or(Vec::new())
+            .iter()
+            .map(|s| s.to_str().to_string())
+            .collect()
+    }
+
     field created_at() -> f64 {
         self.created_at as f64
     }
@@ -134,3 +447,29 @@ graphql_object!(ApiToken: Ctx |&self| {
         self.created_by.clone()
     }
 });
+
+graphql_object!(ApiTokenInfo: Ctx |&self| {
+    field id() -> String {
+        self.0.id.clone()
+    }
+
+    field name() -> Option<String> {
+        self.0.name.clone()
+    }
+
+    field scopes() -> Vec<String> {
+        self.0.scope_list()
+            .unwrap_or(Vec::new())
+            .iter()
+            .map(|s| s.to_str().to_string())
+            .collect()
+    }
+
+    field created_at() -> f64 {
+        self.0.created_at as f64
+    }
+
+    field expires_at() -> Option<f64> {
+        self.0.expires_at.map(|x| x as f64)
+    }
+});
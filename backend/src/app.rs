@@ -1,4 +1,6 @@
 use std::process::exit;
+use std::fs::File;
+use std::io::Read;
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -25,7 +27,7 @@ impl AppInner {
 
     /// Initialize a new app.
     /// This will read config from the environment and initialize the database.
-    pub fn run() {
+    pub fn build() -> App {
 
         // Build the config.
         let config = match Config::from_env() {
@@ -62,7 +64,7 @@ impl AppInner {
 
         app.repo().ensure_admin_user().unwrap();
 
-        ::server::run(app);
+        app
     }
 }
 
@@ -83,6 +85,56 @@ impl Deref for App {
     }
 }
 
+/// Restore a backup file produced by /export/all, as the admin user.
+/// New users are only created without a password if `force` is set.
+fn restore(app: &App, path: &str, force: bool) -> Result<()> {
+    let mut content = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .chain_err(|| format!("Could not read {}", path))?;
+
+    let admin = match app.db()?.user_by_username("admin")? {
+        Some(u) => u,
+        None => {
+            return Err("The admin user does not exist".into());
+        },
+    };
+    let report = app.repo().restore(&content, force, Some(&admin))?;
+    println!("{}", ::serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Run the app.
+///
+/// Commands:
+/// * `serve` (default): start the server
+/// * `restore [--force] <file>`: restore a backup file. `--force` creates
+///   users even if the backup has no password hashes.
 pub fn run() {
-    AppInner::run();
+    let args: Vec<String> = ::std::env::args().collect();
+    let app = AppInner::build();
+
+    match args.get(1).map(|x| x.as_str()) {
+        None | Some("serve") => {
+            ::server::run(app);
+        },
+        Some("restore") => {
+            let force = args[2..].iter().any(|a| a == "--force");
+            let path = match args[2..].iter().find(|a| !a.starts_with("--")) {
+                Some(p) => p,
+                None => {
+                    eprintln!("Usage: translator restore [--force] <file>");
+                    exit(1);
+                },
+            };
+            if let Err(e) = restore(&app, path, force) {
+                eprintln!("Restore failed: {}", e);
+                exit(1);
+            }
+        },
+        Some(cmd) => {
+            eprintln!("Unknown command: {}", cmd);
+            exit(1);
+        },
+    }
 }
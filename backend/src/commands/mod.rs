@@ -7,21 +7,22 @@ use serde_json::Value;
 use ::error::*;
 use ::app::App;
 use db::{Db};
-use ::db::schema::{User};
+use ::db::schema::{User, ApiToken};
 use ::repo::Repo;
 
 pub struct Ctx {
     app: App,
     user: Option<User>,
+    token: Option<ApiToken>,
 }
 
 impl Ctx {
-    pub fn new(app: App, user: Option<User>) -> Self {
-        Ctx { app, user }
+    pub fn new(app: App, user: Option<User>, token: Option<ApiToken>) -> Self {
+        Ctx { app, user, token }
     }
 
     pub fn repo(&self) -> Repo {
-        self.app.repo()
+        self.app.repo().with_token(self.token.as_ref())
     }
 
     pub fn db(&self) -> Result<Db> {
@@ -31,6 +32,11 @@ impl Ctx {
     pub fn user(&self) -> Option<&User> {
         self.user.as_ref()
     }
+
+    /// The token the current request was authenticated with.
+    pub fn token(&self) -> Option<&ApiToken> {
+        self.token.as_ref()
+    }
 }
 
 pub trait CommandExecutor: Serialize + DeserializeOwned + Debug {
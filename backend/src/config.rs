@@ -14,6 +14,10 @@ pub struct Config {
     /// The secret used for generating tokens.
     /// Env var: TRANSLATOR_SECRET.
     pub secret: String,
+    /// The lifetime of session tokens in seconds.
+    /// Defaults to 7 days.
+    /// Env var: TRANSLATOR_SESSION_LIFETIME.
+    pub session_lifetime: i64,
     /// The URL used.
     /// Env var: TRANSLATOR_PUBLIC_API_URL.
     pub public_api_url: String,
@@ -56,6 +60,20 @@ impl Config {
             },
         };
 
+        let session_lifetime = match var("TRANSLATOR_SESSION_LIFETIME") {
+            Ok(l) => {
+                match l.parse() {
+                    Ok(l) if l > 0 => l,
+                    _ => {
+                        return Err("Invalid env var: TRANSLATOR_SESSION_LIFETIME: must be a positive number of seconds".into());
+                    }
+                }
+            },
+            Err(_) => {
+                60 * 60 * 24 * 7
+            },
+        };
+
         let public_api_url = match var("TRANSLATOR_PUBLIC_API_URL") {
             Ok(u) => u.trim().to_string(),
             Err(_) => {
@@ -85,6 +103,7 @@ impl Config {
         Ok(Config {
             port,
             secret,
+            session_lifetime,
             public_api_url,
             admin_password,
             data_path,
@@ -1,8 +1,10 @@
 use std::time::Duration;
+use std::collections::{HashMap, HashSet};
 
 use diesel;
 use diesel::prelude::*;
 use chrono::{Utc};
+use serde_json::{self, Value};
 
 use r2d2::{self, PooledConnection};
 use diesel::sqlite::SqliteConnection;
@@ -78,15 +80,65 @@ pub struct TranslationData {
     translations: Vec<Translation>,
 }
 
+/// The version of the export format.
+/// Must be bumped whenever exports of older versions can no longer be restored.
+pub const EXPORT_VERSION: u64 = 1;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Export {
     pub version: u64,
+    pub projects: Vec<Project>,
+    pub project_members: Vec<ProjectMember>,
+    #[serde(default)]
+    pub project_settings: Vec<ProjectSettings>,
     pub languages: Vec<Language>,
     pub keys: Vec<Key>,
     pub translations: Vec<Translation>,
+    #[serde(default)]
+    pub plural_forms: Vec<PluralForm>,
+    #[serde(default)]
+    pub translation_revisions: Vec<TranslationRevision>,
     pub users: Vec<User>,
 }
 
+/// Parse an export, refusing versions that can not be restored.
+pub fn parse_export(content: &str) -> Result<Export> {
+    let data: Value = serde_json::from_str(content)?;
+    match data.get("version").and_then(|v| v.as_u64()) {
+        Some(v) if v == EXPORT_VERSION => {},
+        Some(v) => {
+            return Err(ErrorKind::IncompatibleExport(v).into());
+        },
+        None => {
+            return Err("Invalid export: missing version".into());
+        },
+    }
+
+    let export = serde_json::from_value(data)?;
+    Ok(export)
+}
+
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct RestoreCount {
+    pub created: usize,
+    pub updated: usize,
+}
+
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct RestoreReport {
+    pub users: RestoreCount,
+    pub projects: RestoreCount,
+    pub project_members: RestoreCount,
+    pub languages: RestoreCount,
+    pub keys: RestoreCount,
+    pub translations: RestoreCount,
+    /// Rows that were matched by a unique name instead of their id.
+    pub remapped: usize,
+    /// Users that were created without a password hash, so they can not log
+    /// in until their password is set.
+    pub users_without_password: Vec<String>,
+}
+
 
 pub struct Db {
     con: PoolConnection,
@@ -107,11 +159,123 @@ impl Db {
         &*self.con
     }
 
-    pub fn languages(&self) -> Result<Vec<Language>> {
+    pub fn projects(&self) -> Result<Vec<Project>> {
+        let projects = projects::table.load(self.con())?;
+        Ok(projects)
+    }
+
+    pub fn project_by_id(&self, id: &str) -> Result<Option<Project>> {
+        use self::projects::dsl;
+        let project = dsl::projects.filter(dsl::id.eq(id))
+            .first(self.con())
+            .optional()?;
+        Ok(project)
+    }
+
+    /// Load all projects the user is a member of.
+    pub fn projects_by_member(&self, user_id: &str) -> Result<Vec<Project>> {
+        use self::project_members::dsl as members;
+        use self::projects::dsl;
+
+        let ids: Vec<String> = members::project_members
+            .filter(members::user_id.eq(user_id))
+            .select(members::project_id)
+            .load(self.con())?;
+        let projects = dsl::projects.filter(dsl::id.eq_any(ids))
+            .load(self.con())?;
+        Ok(projects)
+    }
+
+    pub fn create_project(&self, project: Project) -> Result<Project> {
+        diesel::insert_into(projects::table).values(&project).execute(self.con())?;
+        Ok(project)
+    }
+
+    pub fn delete_project(&self, id: &str) -> Result<()> {
+        use self::projects::dsl;
+
+        diesel::delete(dsl::projects.filter(dsl::id.eq(id)))
+            .execute(self.con())?;
+        Ok(())
+    }
+
+    pub fn all_project_settings(&self) -> Result<Vec<ProjectSettings>> {
+        let settings = project_settings::table.load(self.con())?;
+        Ok(settings)
+    }
+
+    /// Load the settings of a project, which are empty if never saved.
+    pub fn project_settings(&self, project_id: &str) -> Result<ProjectSettings> {
+        use self::project_settings::dsl;
+        let settings = dsl::project_settings
+            .filter(dsl::project_id.eq(project_id))
+            .first(self.con())
+            .optional()?;
+        Ok(settings.unwrap_or(ProjectSettings::new(project_id.to_string())))
+    }
+
+    pub fn save_project_settings(&self, settings: &ProjectSettings) -> Result<()> {
+        diesel::replace_into(project_settings::table).values(settings).execute(self.con())?;
+        Ok(())
+    }
+
+    pub fn all_project_members(&self) -> Result<Vec<ProjectMember>> {
+        let members = project_members::table.load(self.con())?;
+        Ok(members)
+    }
+
+    pub fn project_member(&self, project_id: &str, user_id: &str) -> Result<Option<ProjectMember>> {
+        use self::project_members::dsl;
+        let member = dsl::project_members
+            .filter(dsl::project_id.eq(project_id))
+            .filter(dsl::user_id.eq(user_id))
+            .first(self.con())
+            .optional()?;
+        Ok(member)
+    }
+
+    /// Load all users that are members of a project.
+    pub fn project_users(&self, project_id: &str) -> Result<Vec<User>> {
+        use self::project_members::dsl as members;
+        use self::users::dsl;
+
+        let ids: Vec<String> = members::project_members
+            .filter(members::project_id.eq(project_id))
+            .select(members::user_id)
+            .load(self.con())?;
+        let users = dsl::users.filter(dsl::id.eq_any(ids))
+            .load(self.con())?;
+        Ok(users)
+    }
+
+    pub fn add_project_member(&self, member: ProjectMember) -> Result<ProjectMember> {
+        diesel::insert_into(project_members::table).values(&member).execute(self.con())?;
+        Ok(member)
+    }
+
+    pub fn remove_project_member(&self, project_id: &str, user_id: &str) -> Result<()> {
+        use self::project_members::dsl;
+
+        let q = dsl::project_members
+            .filter(dsl::project_id.eq(project_id))
+            .filter(dsl::user_id.eq(user_id));
+        diesel::delete(q).execute(self.con())?;
+        Ok(())
+    }
+
+    pub fn all_languages(&self) -> Result<Vec<Language>> {
         let langs: Vec<Language> = languages::table.load(self.con())?;
         Ok(langs)
     }
 
+    pub fn languages(&self, project_id: &str) -> Result<Vec<Language>> {
+        use self::languages::dsl;
+        let langs: Vec<Language> = dsl::languages
+            .filter(dsl::project_id.eq(project_id))
+            .load(self.con())?;
+        Ok(langs)
+    }
+
     pub fn base_data(&self) -> Result<BaseData> {
         let langs: Vec<Language> = languages::table.load(self.con())?;
         let keys: Vec<Key> = keys::table.load(self.con())?;
@@ -147,22 +311,11 @@ impl Db {
         Ok(user)
     }
 
-    pub fn update_user(&self, username: &str, password: &str) -> Result<()> {
+    pub fn update_user(&self, user: &User) -> Result<()> {
         use self::users::dsl;
 
-        let res = self.user_by_username(username)?;
-        let mut user = match res {
-            Some(u) => u,
-            None => {
-                let err: ::error::Error = ::error::ErrorKind::UnknownUser.into();
-                return Err(err.into());
-            },
-        };
-
-        user.set_password(password);
-
-        diesel::update(dsl::users.filter(dsl::username.eq(&user.username)))
-            .set(&user)
+        diesel::update(dsl::users.filter(dsl::id.eq(&user.id)))
+            .set(user)
             .execute(self.con())?;
         Ok(())
     }
@@ -184,12 +337,39 @@ impl Db {
         Ok(res)
     }
 
+    pub fn api_token_by_id(&self, id: &str) -> Result<Option<ApiToken>>
+    {
+        use self::api_tokens::dsl;
+        let res = dsl::api_tokens.filter(dsl::id.eq(id))
+            .first(self.con()).optional()?;
+        Ok(res)
+    }
+
     pub fn create_api_token(&self, token: ApiToken) -> Result<ApiToken>
     {
         diesel::insert_into(api_tokens::table).values(&token).execute(self.con())?;
         Ok(token)
     }
 
+    pub fn api_tokens_by_user(&self, user_id: &str, kind: TokenKind) -> Result<Vec<ApiToken>>
+    {
+        use self::api_tokens::dsl;
+        let res = dsl::api_tokens
+            .filter(dsl::created_by.eq(user_id))
+            .filter(dsl::kind.eq(kind.to_str()))
+            .load(self.con())?;
+        Ok(res)
+    }
+
+    pub fn delete_api_token(&self, token: &str) -> Result<()>
+    {
+        use self::api_tokens::dsl;
+
+        diesel::delete(dsl::api_tokens.filter(dsl::token.eq(token)))
+            .execute(self.con())?;
+        Ok(())
+    }
+
     pub fn language_by_id(&self, id: &str) -> Result<Option<Language>> {
         use self::languages::dsl;
         let lang = dsl::languages.filter(dsl::id.eq(id))
@@ -198,6 +378,16 @@ impl Db {
         Ok(lang)
     }
 
+    pub fn language_by_code(&self, project_id: &str, code: &str) -> Result<Option<Language>> {
+        use self::languages::dsl;
+        let lang = dsl::languages
+            .filter(dsl::project_id.eq(project_id))
+            .filter(dsl::code.eq(code))
+            .first(self.con())
+            .optional()?;
+        Ok(lang)
+    }
+
     pub fn create_language(&self, lang: Language) -> Result<Language>
     {
         diesel::insert_into(languages::table).values(&lang).execute(self.con())?;
@@ -212,14 +402,24 @@ impl Db {
         Ok(())
     }
 
-    pub fn keys(&self) -> Result<Vec<Key>> {
+    pub fn all_keys(&self) -> Result<Vec<Key>> {
         let keys: Vec<Key> = keys::table.load(self.con())?;
         Ok(keys)
     }
 
-    pub fn key_by_key(&self, key: &str) -> Result<Option<Key>> {
+    pub fn keys(&self, project_id: &str) -> Result<Vec<Key>> {
+        use self::keys::dsl;
+        let keys: Vec<Key> = dsl::keys
+            .filter(dsl::project_id.eq(project_id))
+            .load(self.con())?;
+        Ok(keys)
+    }
+
+    pub fn key_by_key(&self, project_id: &str, key: &str) -> Result<Option<Key>> {
         use self::keys::dsl;
-        let key = dsl::keys.filter(dsl::key.eq(key))
+        let key = dsl::keys
+            .filter(dsl::project_id.eq(project_id))
+            .filter(dsl::key.eq(key))
             .first(self.con())
             .optional()?;
         Ok(key)
@@ -255,6 +455,25 @@ impl Db {
         Ok(key)
     }
 
+    pub fn set_key_plural(&self, id: &str, plural: bool) -> Result<()> {
+        diesel::update(keys::table.filter(keys::id.eq(id)))
+            .set(keys::plural.eq(plural))
+            .execute(self.con())?;
+        if plural {
+            diesel::update(translations::table.filter(translations::key_id.eq(id)))
+                .set(translations::outdated.eq(true))
+                .execute(self.con())?;
+        } else {
+            let ids: Vec<String> = translations::table
+                .select(translations::id)
+                .filter(translations::key_id.eq(id))
+                .load(self.con())?;
+            diesel::delete(plural_forms::table.filter(plural_forms::translation_id.eq_any(ids)))
+                .execute(self.con())?;
+        }
+        Ok(())
+    }
+
     pub fn delete_key(&self, id: &str) -> Result<()> {
         use self::keys::dsl;
 
@@ -263,6 +482,14 @@ impl Db {
         Ok(())
     }
 
+    pub fn translation_by_id(&self, id: &str) -> Result<Option<Translation>> {
+        use self::translations::dsl;
+        let trans = dsl::translations.filter(dsl::id.eq(id))
+            .first(self.con())
+            .optional()?;
+        Ok(trans)
+    }
+
     pub fn find_translation(&self, key_id: &str, lang_id: &str) -> Result<Option<Translation>> {
         use self::translations::dsl;
         let trans: Option<Translation> =
@@ -308,15 +535,150 @@ impl Db {
         Ok(translation)
     }
 
-    pub fn update_translation(&self, id: &str, value: &str) -> Result<()> {
+    /// Save the value, version, update time, review and source version of a
+    /// translation, unless its stored version is no longer `current_version`.
+    /// Returns whether the translation was saved.
+    pub fn update_translation(&self, translation: &Translation, current_version: i32) -> Result<bool> {
+        use self::translations::dsl;
+
+        let q = dsl::translations
+            .filter(dsl::id.eq(&translation.id))
+            .filter(dsl::version.eq(current_version));
+
+        let count = diesel::update(q)
+            .set((
+                dsl::value.eq(&translation.value),
+                dsl::version.eq(translation.version),
+                dsl::updated_at.eq(translation.updated_at),
+                dsl::status.eq(&translation.status),
+                dsl::review_comment.eq(&translation.review_comment),
+                dsl::reviewed_at.eq(translation.reviewed_at),
+                dsl::reviewed_by.eq(&translation.reviewed_by),
+                dsl::source_version.eq(translation.source_version),
+                dsl::outdated.eq(translation.outdated),
+                dsl::approved_version.eq(translation.approved_version),
+            ))
+            .execute(self.con())?;
+        Ok(count > 0)
+    }
+
+    /// Flag the translations of a key as outdated, except for the one in the
+    /// source language.
+    pub fn mark_translations_outdated(&self, key_id: &str, source_language_id: &str) -> Result<()> {
         use self::translations::dsl;
 
         let q = dsl::translations
-            .filter(dsl::id.eq(id));
+            .filter(dsl::key_id.eq(key_id))
+            .filter(dsl::language_id.ne(source_language_id));
 
         diesel::update(q)
-            .set(dsl::value.eq(value))
+            .set(dsl::outdated.eq(true))
+            .execute(self.con())?;
+        Ok(())
+    }
+
+    /// Base the translations of a project on the current values of a new
+    /// source language, clearing their outdated flags. Translations in the
+    /// source language itself have no source version.
+    pub fn reset_source_versions(&self, project_id: &str, source_language_id: Option<&str>) -> Result<()> {
+        use self::translations::dsl;
+
+        let lang_ids: Vec<String> = self.languages(project_id)?.into_iter().map(|l| l.id).collect();
+        diesel::update(dsl::translations.filter(dsl::language_id.eq_any(&lang_ids)))
+            .set((
+                dsl::source_version.eq(None::<i32>),
+                dsl::outdated.eq(false),
+            ))
             .execute(self.con())?;
+
+        let source_id = match source_language_id {
+            Some(id) => id,
+            None => {
+                return Ok(());
+            },
+        };
+        let sources: Vec<Translation> = dsl::translations
+            .filter(dsl::language_id.eq(source_id))
+            .load(self.con())?;
+        for source in sources {
+            let q = dsl::translations
+                .filter(dsl::key_id.eq(&source.key_id))
+                .filter(dsl::language_id.ne(source_id));
+            diesel::update(q)
+                .set(dsl::source_version.eq(source.version))
+                .execute(self.con())?;
+        }
+        Ok(())
+    }
+
+    pub fn outdated_translations(&self, lang_id: &str) -> Result<Vec<Translation>> {
+        use self::translations::dsl;
+        let trans = dsl::translations
+            .filter(dsl::language_id.eq(lang_id))
+            .filter(dsl::outdated.eq(true))
+            .load(self.con())?;
+        Ok(trans)
+    }
+
+    pub fn all_translation_revisions(&self) -> Result<Vec<TranslationRevision>> {
+        let revisions = translation_revisions::table.load(self.con())?;
+        Ok(revisions)
+    }
+
+    /// Load the revisions of a translation, newest first.
+    pub fn translation_revisions(&self, translation_id: &str) -> Result<Vec<TranslationRevision>> {
+        use self::translation_revisions::dsl;
+        let revisions = dsl::translation_revisions
+            .filter(dsl::translation_id.eq(translation_id))
+            .order(dsl::version.desc())
+            .load(self.con())?;
+        Ok(revisions)
+    }
+
+    pub fn translation_revision(&self, translation_id: &str, version: i32) -> Result<Option<TranslationRevision>> {
+        use self::translation_revisions::dsl;
+        let revision = dsl::translation_revisions
+            .filter(dsl::translation_id.eq(translation_id))
+            .filter(dsl::version.eq(version))
+            .first(self.con())
+            .optional()?;
+        Ok(revision)
+    }
+
+    pub fn create_translation_revision(&self, revision: &TranslationRevision) -> Result<()> {
+        diesel::insert_into(translation_revisions::table).values(revision).execute(self.con())?;
+        Ok(())
+    }
+
+    pub fn all_plural_forms(&self) -> Result<Vec<PluralForm>> {
+        let forms = plural_forms::table.load(self.con())?;
+        Ok(forms)
+    }
+
+    pub fn plural_forms(&self, translation_id: &str) -> Result<Vec<PluralForm>> {
+        use self::plural_forms::dsl;
+        let forms = dsl::plural_forms
+            .filter(dsl::translation_id.eq(translation_id))
+            .load(self.con())?;
+        Ok(forms)
+    }
+
+    /// Load the plural forms of all translations of a language.
+    pub fn plural_forms_by_lang(&self, lang_id: &str) -> Result<Vec<PluralForm>> {
+        let forms = plural_forms::table
+            .inner_join(translations::table)
+            .filter(translations::language_id.eq(lang_id))
+            .select(plural_forms::all_columns)
+            .load(self.con())?;
+        Ok(forms)
+    }
+
+    /// Replace the plural forms of a translation.
+    pub fn set_plural_forms(&self, translation_id: &str, forms: &[PluralForm]) -> Result<()> {
+        use self::plural_forms::dsl;
+        diesel::delete(dsl::plural_forms.filter(dsl::translation_id.eq(translation_id)))
+            .execute(self.con())?;
+        diesel::insert_into(plural_forms::table).values(forms).execute(self.con())?;
         Ok(())
     }
 
@@ -329,15 +691,321 @@ impl Db {
 
     pub fn export(&self) -> Result<Export> {
         let exp = Export{
-            version: 0,
-            languages: self.languages()?,
-            keys: self.keys()?,
+            version: EXPORT_VERSION,
+            projects: self.projects()?,
+            project_members: self.all_project_members()?,
+            project_settings: self.all_project_settings()?,
+            languages: self.all_languages()?,
+            keys: self.all_keys()?,
             translations: self.all_translations()?,
+            plural_forms: self.all_plural_forms()?,
+            translation_revisions: self.all_translation_revisions()?,
             users: self.users()?,
         };
         Ok(exp)
     }
 
+    /// Start a transaction, or a savepoint if a transaction is already open.
+    /// Must be followed by `commit_transaction` or `rollback_transaction`.
+    pub fn begin_transaction(&self) -> Result<()> {
+        use diesel::connection::TransactionManager;
+        let con = self.con();
+        con.transaction_manager().begin_transaction(con)?;
+        Ok(())
+    }
+
+    pub fn commit_transaction(&self) -> Result<()> {
+        use diesel::connection::TransactionManager;
+        let con = self.con();
+        con.transaction_manager().commit_transaction(con)?;
+        Ok(())
+    }
+
+    pub fn rollback_transaction(&self) -> Result<()> {
+        use diesel::connection::TransactionManager;
+        let con = self.con();
+        con.transaction_manager().rollback_transaction(con)?;
+        Ok(())
+    }
+
+    /// Restore an export within a single transaction.
+    ///
+    /// Rows are matched by id, or by their unique name (username, project name,
+    /// language code, key) if the ids differ. Matched rows are overwritten with
+    /// the exported data, and references are rewritten to the ids of the matched
+    /// rows.
+    ///
+    /// Creating users from an export without password hashes fails, unless
+    /// `force` is set.
+    pub fn restore(&self, export: Export, force: bool) -> Result<RestoreReport> {
+        self.con().transaction::<_, Error, _>(|| self.restore_rows(export, force))
+    }
+
+    fn restore_rows(&self, export: Export, force: bool) -> Result<RestoreReport> {
+        let mut report = RestoreReport::default();
+
+        // Maps from exported ids to database ids.
+        let mut user_ids = HashMap::new();
+        let mut usernames = HashMap::new();
+        let mut project_ids = HashMap::new();
+        let mut language_ids = HashMap::new();
+        let mut key_ids = HashMap::new();
+        let mut translation_ids = HashMap::new();
+
+        let existing = self.users()?;
+        for user in export.users {
+            let found = existing.iter()
+                .find(|e| e.id == user.id || e.username == user.username)
+                .cloned();
+            match found {
+                Some(mut e) => {
+                    if e.id != user.id {
+                        report.remapped += 1;
+                    }
+                    user_ids.insert(user.id.clone(), e.id.clone());
+                    usernames.insert(user.username.clone(), e.username.clone());
+
+                    e.role = user.role;
+                    // Exports only contain hashes when explicitly requested.
+                    if user.password_hash != "" {
+                        e.password_hash = user.password_hash;
+                    }
+                    self.update_user(&e)?;
+                    report.users.updated += 1;
+                },
+                None => {
+                    if user.password_hash == "" {
+                        report.users_without_password.push(user.username.clone());
+                    }
+                    user_ids.insert(user.id.clone(), user.id.clone());
+                    usernames.insert(user.username.clone(), user.username.clone());
+                    diesel::insert_into(users::table).values(&user).execute(self.con())?;
+                    report.users.created += 1;
+                },
+            }
+        }
+        if !force && !report.users_without_password.is_empty() {
+            return Err(format!("Users {} would be created without a password, as the export contains no password hashes",
+                               report.users_without_password.join(", ")).into());
+        }
+
+        // Users that are neither exported nor in the database are dropped from references.
+        let known_users: HashSet<String> = self.users()?.into_iter().map(|u| u.id).collect();
+        let map_user = |id: Option<String>| -> Option<String> {
+            id.map(|id| user_ids.get(&id).cloned().unwrap_or(id))
+              .and_then(|id| if known_users.contains(&id) { Some(id) } else { None })
+        };
+
+        let existing = self.projects()?;
+        for mut project in export.projects {
+            project.created_by = map_user(project.created_by);
+            let found = existing.iter().find(|e| e.id == project.id || e.name == project.name);
+            match found {
+                Some(e) => {
+                    if e.id != project.id {
+                        report.remapped += 1;
+                    }
+                    project_ids.insert(project.id.clone(), e.id.clone());
+                    project.id = e.id.clone();
+                    diesel::update(projects::table.filter(projects::id.eq(&project.id)))
+                        .set(&project)
+                        .execute(self.con())?;
+                    report.projects.updated += 1;
+                },
+                None => {
+                    project_ids.insert(project.id.clone(), project.id.clone());
+                    self.create_project(project)?;
+                    report.projects.created += 1;
+                },
+            }
+        }
+
+        for mut member in export.project_members {
+            let ids = (project_ids.get(&member.project_id), user_ids.get(&member.user_id));
+            if let (Some(project_id), Some(user_id)) = ids {
+                member.project_id = project_id.clone();
+                member.user_id = user_id.clone();
+            } else {
+                return Err("Invalid export: project member references unknown project or user".into());
+            }
+
+            if self.project_member(&member.project_id, &member.user_id)?.is_none() {
+                self.add_project_member(member)?;
+                report.project_members.created += 1;
+            }
+        }
+
+        // Languages are first restored without parents, since parents may not exist yet.
+        let existing = self.all_languages()?;
+        let mut parents = Vec::new();
+        for mut lang in export.languages {
+            lang.project_id = match project_ids.get(&lang.project_id) {
+                Some(id) => id.clone(),
+                None => {
+                    return Err("Invalid export: language references unknown project".into());
+                },
+            };
+            lang.created_by = map_user(lang.created_by);
+
+            let found = existing.iter()
+                .find(|e| e.id == lang.id || (e.project_id == lang.project_id && e.code == lang.code))
+                .map(|e| e.id.clone());
+            let exported_id = lang.id.clone();
+            if let Some(parent_id) = lang.parent_id.take() {
+                parents.push((exported_id.clone(), parent_id));
+            }
+
+            match found {
+                Some(id) => {
+                    if id != lang.id {
+                        report.remapped += 1;
+                    }
+                    lang.id = id.clone();
+                    diesel::update(languages::table.filter(languages::id.eq(&id)))
+                        .set(&lang)
+                        .execute(self.con())?;
+                    language_ids.insert(exported_id, id);
+                    report.languages.updated += 1;
+                },
+                None => {
+                    language_ids.insert(exported_id, lang.id.clone());
+                    self.create_language(lang)?;
+                    report.languages.created += 1;
+                },
+            }
+        }
+
+        for (lang_id, parent_id) in parents {
+            let lang_id = &language_ids[&lang_id];
+            let parent_id = match language_ids.get(&parent_id) {
+                Some(id) => id,
+                None => {
+                    return Err("Invalid export: language references unknown parent".into());
+                },
+            };
+            diesel::update(languages::table.filter(languages::id.eq(lang_id)))
+                .set(languages::parent_id.eq(parent_id))
+                .execute(self.con())?;
+        }
+
+        for mut settings in export.project_settings {
+            settings.project_id = match project_ids.get(&settings.project_id) {
+                Some(id) => id.clone(),
+                None => {
+                    return Err("Invalid export: project settings reference unknown project".into());
+                },
+            };
+            if let Some(id) = settings.default_language_id.take() {
+                match language_ids.get(&id) {
+                    Some(id) => settings.default_language_id = Some(id.clone()),
+                    None => {
+                        return Err("Invalid export: project settings reference unknown language".into());
+                    },
+                }
+            }
+            if let Some(id) = settings.source_language_id.take() {
+                match language_ids.get(&id) {
+                    Some(id) => settings.source_language_id = Some(id.clone()),
+                    None => {
+                        return Err("Invalid export: project settings reference unknown language".into());
+                    },
+                }
+            }
+            self.save_project_settings(&settings)?;
+        }
+
+        let existing = self.all_keys()?;
+        for mut key in export.keys {
+            key.project_id = match project_ids.get(&key.project_id) {
+                Some(id) => id.clone(),
+                None => {
+                    return Err("Invalid export: key references unknown project".into());
+                },
+            };
+            key.created_by = key.created_by.and_then(|name| usernames.get(&name).cloned());
+
+            let found = existing.iter()
+                .find(|e| e.id == key.id || (e.project_id == key.project_id && e.key == key.key))
+                .map(|e| e.id.clone());
+            match found {
+                Some(id) => {
+                    if id != key.id {
+                        report.remapped += 1;
+                    }
+                    key_ids.insert(key.id.clone(), id.clone());
+                    key.id = id.clone();
+                    diesel::update(keys::table.filter(keys::id.eq(&id)))
+                        .set(&key)
+                        .execute(self.con())?;
+                    report.keys.updated += 1;
+                },
+                None => {
+                    key_ids.insert(key.id.clone(), key.id.clone());
+                    diesel::insert_into(keys::table).values(&key).execute(self.con())?;
+                    report.keys.created += 1;
+                },
+            }
+        }
+
+        let existing = self.all_translations()?;
+        for mut t in export.translations {
+            let ids = (language_ids.get(&t.language_id), key_ids.get(&t.key_id));
+            if let (Some(language_id), Some(key_id)) = ids {
+                t.language_id = language_id.clone();
+                t.key_id = key_id.clone();
+            } else {
+                return Err("Invalid export: translation references unknown language or key".into());
+            }
+            t.created_by = map_user(t.created_by);
+            t.reviewed_by = map_user(t.reviewed_by);
+
+            let found = existing.iter()
+                .find(|e| e.id == t.id || (e.language_id == t.language_id && e.key_id == t.key_id))
+                .map(|e| e.id.clone());
+            match found {
+                Some(id) => {
+                    if id != t.id {
+                        report.remapped += 1;
+                    }
+                    translation_ids.insert(t.id.clone(), id.clone());
+                    t.id = id.clone();
+                    diesel::update(translations::table.filter(translations::id.eq(&id)))
+                        .set(&t)
+                        .execute(self.con())?;
+                    report.translations.updated += 1;
+                },
+                None => {
+                    translation_ids.insert(t.id.clone(), t.id.clone());
+                    self.create_translation(t)?;
+                    report.translations.created += 1;
+                },
+            }
+        }
+
+        for mut form in export.plural_forms {
+            form.translation_id = match translation_ids.get(&form.translation_id) {
+                Some(id) => id.clone(),
+                None => {
+                    return Err("Invalid export: plural form references unknown translation".into());
+                },
+            };
+            diesel::replace_into(plural_forms::table).values(&form).execute(self.con())?;
+        }
+
+        for mut revision in export.translation_revisions {
+            revision.translation_id = match translation_ids.get(&revision.translation_id) {
+                Some(id) => id.clone(),
+                None => {
+                    return Err("Invalid export: revision references unknown translation".into());
+                },
+            };
+            revision.created_by = map_user(revision.created_by);
+            diesel::replace_into(translation_revisions::table).values(&revision).execute(self.con())?;
+        }
+
+        Ok(report)
+    }
+
     /*
 
     pub fn command(&self, cmd: &Command)
@@ -1,4 +1,6 @@
 
+use super::Permission;
+
 table!{
     api_tokens(token) {
         token -> Text,
@@ -6,6 +8,9 @@ table!{
         created_at -> BigInt,
         expires_at -> Nullable<BigInt>,
         created_by -> Nullable<Text>,
+        name -> Nullable<Text>,
+        scopes -> Nullable<Text>,
+        id -> Text,
     }
 }
 
@@ -22,6 +27,58 @@ impl TokenKind {
             TokenKind::Api => "api",
         }
     }
+
+    pub fn from_str<S: AsRef<str>>(raw: S) -> Option<TokenKind> {
+        match raw.as_ref() {
+            "session" => Some(TokenKind::Session),
+            "api" => Some(TokenKind::Api),
+            _ => None,
+        }
+    }
+}
+
+/// Restricts what an api token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Read languages, keys and translations through the api.
+    Read,
+    /// Use the export endpoints.
+    ExportRead,
+    /// Create, rename and delete keys.
+    KeysWrite,
+    /// Create, update and delete translations.
+    TranslationsWrite,
+}
+
+impl Scope {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            Scope::Read => "read",
+            Scope::ExportRead => "export:read",
+            Scope::KeysWrite => "keys:write",
+            Scope::TranslationsWrite => "translations:write",
+        }
+    }
+
+    pub fn from_str<S: AsRef<str>>(raw: S) -> Option<Scope> {
+        match raw.as_ref() {
+            "read" => Some(Scope::Read),
+            "export:read" => Some(Scope::ExportRead),
+            "keys:write" => Some(Scope::KeysWrite),
+            "translations:write" => Some(Scope::TranslationsWrite),
+            _ => None,
+        }
+    }
+
+    pub fn grants(&self, perm: Permission) -> bool {
+        match (*self, perm) {
+            (Scope::Read, Permission::Read) => true,
+            (Scope::ExportRead, Permission::Export) => true,
+            (Scope::KeysWrite, Permission::ManageKeys) => true,
+            (Scope::TranslationsWrite, Permission::Translate) => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Insertable, Queryable, AsChangeset,
@@ -33,4 +90,25 @@ pub struct ApiToken {
     pub created_at: i64,
     pub expires_at: Option<i64>,
     pub created_by: Option<String>,
+    pub name: Option<String>,
+    /// Space separated list of scopes.
+    /// Only set for api tokens, session tokens are unrestricted.
+    pub scopes: Option<String>,
+    /// Identifies the token without revealing it.
+    pub id: String,
+}
+
+/// An api token without its secret, as listed to its owner.
+/// The secret is only returned when the token is created.
+pub struct ApiTokenInfo(pub ApiToken);
+
+impl ApiToken {
+    /// The scopes of the token, or None if the token is not restricted.
+    pub fn scope_list(&self) -> Option<Vec<Scope>> {
+        self.scopes.as_ref().map(|s| {
+            s.split_whitespace()
+             .filter_map(Scope::from_str)
+             .collect()
+        })
+    }
 }
@@ -1,11 +1,13 @@
 
 table!(
-  keys(key) {
+  keys {
     id -> Text,
+    project_id -> Text,
     key -> Text,
     description -> Nullable<Text>,
     created_at -> BigInt,
     created_by -> Nullable<Text>,
+    plural -> Bool,
   }
 );
 
@@ -14,16 +16,23 @@ table!(
 #[table_name="keys"]
 pub struct Key {
     pub id: String,
+    pub project_id: String,
     pub key: String,
     pub description: Option<String>,
     pub created_at: i64,
     pub created_by: Option<String>,
+    /// Plural keys have a value per plural category of each language.
+    #[serde(default)]
+    pub plural: bool,
 }
 
 #[derive(GraphQLInputObject, Debug, Clone)]
 pub struct NewKey {
+    /// Defaults to the default project.
+    pub project_id: Option<String>,
     pub key: String,
     pub description: Option<String>,
+    pub plural: Option<bool>,
 }
 
 pub fn validate_key(key: &str) -> bool {
@@ -2,6 +2,7 @@
 table!(
   languages {
     id -> Text,
+    project_id -> Text,
     code -> Text,
     name -> Text,
     parent_id -> Nullable<Text>,
@@ -15,6 +16,7 @@ Serialize, Deserialize, Debug, Clone)]
 #[table_name="languages"]
 pub struct Language {
     pub id: String,
+    pub project_id: String,
     pub code: String,
     pub name: String,
     pub parent_id: Option<String>,
@@ -24,6 +26,8 @@ pub struct Language {
 
 #[derive(GraphQLInputObject, Debug, Clone)]
 pub struct NewLanguage {
+    /// Defaults to the default project.
+    pub project_id: Option<String>,
     pub code: String,
     pub name: String,
     pub parent_id: Option<String>,
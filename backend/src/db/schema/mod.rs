@@ -1,5 +1,8 @@
 pub mod user;
-pub use self::user::{users, User, Role};
+pub use self::user::{users, User, Role, Permission};
+
+pub mod project;
+pub use self::project::{projects, project_members, project_settings, Project, ProjectMember, ProjectSettings, PlaceholderSyntax, NewProject, DEFAULT_PROJECT};
 
 pub mod language;
 pub use self::language::{languages, Language, NewLanguage};
@@ -8,7 +11,7 @@ pub mod key;
 pub use self::key::{keys, Key, NewKey};
 
 pub mod translation;
-pub use self::translation::{translations, Translation, NewTranslation};
+pub use self::translation::{translations, plural_forms, translation_revisions, Translation, TranslationStatus, PluralForm, TranslationRevision, PluralValue, NewTranslation};
 
 pub mod api_token;
-pub use self::api_token::{api_tokens, ApiToken, TokenKind};
+pub use self::api_token::{api_tokens, ApiToken, ApiTokenInfo, TokenKind, Scope};
@@ -0,0 +1,116 @@
+/// The project that data created before projects existed was moved into.
+pub const DEFAULT_PROJECT: &'static str = "default";
+
+table!(
+  projects {
+    id -> Text,
+    name -> Text,
+    created_at -> BigInt,
+    created_by -> Nullable<Text>,
+  }
+);
+
+table!(
+  project_members(project_id, user_id) {
+    project_id -> Text,
+    user_id -> Text,
+    created_at -> BigInt,
+  }
+);
+
+table!(
+  project_settings(project_id) {
+    project_id -> Text,
+    default_language_id -> Nullable<Text>,
+    placeholder_syntax -> Text,
+    source_language_id -> Nullable<Text>,
+  }
+);
+
+#[derive(Insertable, Queryable, AsChangeset,
+Serialize, Deserialize, Debug, Clone)]
+#[table_name="projects"]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub created_by: Option<String>,
+}
+
+#[derive(Insertable, Queryable,
+Serialize, Deserialize, Debug, Clone)]
+#[table_name="project_members"]
+pub struct ProjectMember {
+    pub project_id: String,
+    pub user_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Insertable, Queryable,
+Serialize, Deserialize, Debug, Clone)]
+#[table_name="project_settings"]
+pub struct ProjectSettings {
+    pub project_id: String,
+    /// Used for exports with fallbacks after the parents of a language.
+    pub default_language_id: Option<String>,
+    #[serde(default = "default_placeholder_syntax")]
+    pub placeholder_syntax: String,
+    /// The language translations are made from. The default language is
+    /// used if not set.
+    #[serde(default)]
+    pub source_language_id: Option<String>,
+}
+
+fn default_placeholder_syntax() -> String {
+    PlaceholderSyntax::Icu.to_str().to_string()
+}
+
+impl ProjectSettings {
+    pub fn new(project_id: String) -> Self {
+        ProjectSettings {
+            project_id,
+            default_language_id: None,
+            placeholder_syntax: default_placeholder_syntax(),
+            source_language_id: None,
+        }
+    }
+
+    pub fn syntax(&self) -> PlaceholderSyntax {
+        PlaceholderSyntax::from_str(&self.placeholder_syntax).unwrap_or(PlaceholderSyntax::Icu)
+    }
+}
+
+/// How placeholders are written in the values of a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceholderSyntax {
+    /// ICU MessageFormat arguments like `{name}`.
+    Icu,
+    /// printf style conversions like `%s` and `%1$d`.
+    Printf,
+    /// Double braces like `{{name}}`.
+    Mustache,
+}
+
+impl PlaceholderSyntax {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            PlaceholderSyntax::Icu => "icu",
+            PlaceholderSyntax::Printf => "printf",
+            PlaceholderSyntax::Mustache => "mustache",
+        }
+    }
+
+    pub fn from_str<S: AsRef<str>>(raw: S) -> Option<PlaceholderSyntax> {
+        match raw.as_ref() {
+            "icu" => Some(PlaceholderSyntax::Icu),
+            "printf" => Some(PlaceholderSyntax::Printf),
+            "mustache" => Some(PlaceholderSyntax::Mustache),
+            _ => None,
+        }
+    }
+}
+
+#[derive(GraphQLInputObject, Debug, Clone)]
+pub struct NewProject {
+    pub name: String,
+}
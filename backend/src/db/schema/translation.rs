@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+
+use serde_json;
 use uuid::Uuid;
 
+use ::error::*;
+
 use super::key::keys;
 
 table!(
@@ -12,11 +17,39 @@ table!(
     created_at -> BigInt,
     updated_at -> BigInt,
     created_by -> Nullable<Text>,
+    status -> Text,
+    review_comment -> Nullable<Text>,
+    reviewed_at -> Nullable<BigInt>,
+    reviewed_by -> Nullable<Text>,
+    source_version -> Nullable<Int4>,
+    outdated -> Bool,
+    approved_version -> Nullable<Int4>,
+  }
+);
+
+table!(
+  plural_forms(translation_id, category) {
+    translation_id -> Text,
+    category -> Text,
+    value -> Text,
+  }
+);
+
+table!(
+  translation_revisions(translation_id, version) {
+    translation_id -> Text,
+    version -> Int4,
+    value -> Text,
+    plural_values -> Nullable<Text>,
+    created_at -> BigInt,
+    created_by -> Nullable<Text>,
   }
 );
 
 joinable!(translations -> super::key::keys (key_id));
+joinable!(plural_forms -> translations (translation_id));
 allow_tables_to_appear_in_same_query!(translations, keys);
+allow_tables_to_appear_in_same_query!(plural_forms, translations);
 
 #[derive(Insertable, Queryable, AsChangeset,
 Serialize, Deserialize, Debug, Clone)]
@@ -30,6 +63,119 @@ pub struct Translation {
     pub created_at: i64,
     pub updated_at: i64,
     pub created_by: Option<String>,
+    /// Translations from before reviews were introduced are approved.
+    #[serde(default = "default_status")]
+    pub status: String,
+    /// The reason given for a rejection.
+    #[serde(default)]
+    pub review_comment: Option<String>,
+    #[serde(default)]
+    pub reviewed_at: Option<i64>,
+    #[serde(default)]
+    pub reviewed_by: Option<String>,
+    /// The version of the source translation the value was made against.
+    #[serde(default)]
+    pub source_version: Option<i32>,
+    /// Whether the source translation changed since.
+    #[serde(default)]
+    pub outdated: bool,
+    /// The last version that was approved.
+    #[serde(default)]
+    pub approved_version: Option<i32>,
+}
+
+fn default_status() -> String {
+    TranslationStatus::Approved.to_str().to_string()
+}
+
+impl Translation {
+    pub fn status(&self) -> TranslationStatus {
+        TranslationStatus::from_str(&self.status).unwrap_or(TranslationStatus::Approved)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranslationStatus {
+    /// Saved, but not yet submitted for review.
+    Draft,
+    NeedsReview,
+    /// Exported when only approved translations are requested.
+    Approved,
+    Rejected,
+}
+
+impl TranslationStatus {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            TranslationStatus::Draft => "draft",
+            TranslationStatus::NeedsReview => "needs-review",
+            TranslationStatus::Approved => "approved",
+            TranslationStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn from_str<S: AsRef<str>>(raw: S) -> Option<TranslationStatus> {
+        match raw.as_ref() {
+            "draft" => Some(TranslationStatus::Draft),
+            "needs-review" => Some(TranslationStatus::NeedsReview),
+            "approved" => Some(TranslationStatus::Approved),
+            "rejected" => Some(TranslationStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a plural translation for one CLDR category.
+/// The value of the translation itself is the `other` form.
+#[derive(Insertable, Queryable,
+Serialize, Deserialize, Debug, Clone)]
+#[table_name="plural_forms"]
+pub struct PluralForm {
+    pub translation_id: String,
+    pub category: String,
+    pub value: String,
+}
+
+/// A value a translation had.
+#[derive(Insertable, Queryable,
+Serialize, Deserialize, Debug, Clone)]
+#[table_name="translation_revisions"]
+pub struct TranslationRevision {
+    pub translation_id: String,
+    pub version: i32,
+    pub value: String,
+    /// The plural values as a json object by category.
+    pub plural_values: Option<String>,
+    pub created_at: i64,
+    pub created_by: Option<String>,
+}
+
+impl TranslationRevision {
+    /// The plural values in canonical category order.
+    pub fn plural_forms(&self) -> Result<Vec<PluralForm>> {
+        let raw = match self.plural_values {
+            Some(ref raw) => raw,
+            None => {
+                return Ok(Vec::new());
+            },
+        };
+        let values: BTreeMap<String, String> = serde_json::from_str(raw)?;
+        let mut forms: Vec<PluralForm> = values.into_iter()
+            .map(|(category, value)| PluralForm {
+                translation_id: self.translation_id.clone(),
+                category,
+                value,
+            })
+            .collect();
+        forms.sort_by_key(|f| ::plurals::CATEGORIES.iter().position(|c| *c == f.category));
+        Ok(forms)
+    }
+}
+
+#[derive(GraphQLInputObject, PartialEq, Eq, Debug, Clone)]
+pub struct PluralValue {
+    pub category: String,
+    pub value: String,
 }
 
 #[derive(GraphQLInputObject, Debug)]
@@ -37,4 +183,12 @@ pub struct NewTranslation {
     pub language_id: Uuid,
     pub key_id: Uuid,
     pub value: String,
+    /// Required for plural keys, with a value for every category of the
+    /// language.
+    pub plural_values: Option<Vec<PluralValue>>,
+    /// The version the new value is based on, or 0 for a new translation.
+    /// The update is rejected if the translation has changed since.
+    pub expected_version: Option<i32>,
+    /// Save the value as a draft instead of submitting it for review.
+    pub draft: Option<bool>,
 }
@@ -13,15 +13,50 @@ table!(
   }
 );
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Role {
+    /// May only read and export data.
+    ReadOnly,
+    /// May translate.
+    Translator,
+    /// May translate and review translations.
+    Reviewer,
+    /// May manage keys.
+    Developer,
+    /// May manage keys and translate.
     User,
+    /// May do everything, including managing users and languages.
     Admin,
 }
 
+/// Operations that are subject to authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Read languages, keys and translations.
+    Read,
+    /// Use the export endpoints.
+    Export,
+    /// Create, update and delete translations.
+    Translate,
+    /// Approve and reject translations.
+    Review,
+    /// Create, rename and delete keys.
+    ManageKeys,
+    /// Create and delete languages.
+    ManageLanguages,
+    /// Manage users and access full database exports.
+    ManageUsers,
+    /// Create and delete projects, manage their members and access all projects.
+    ManageProjects,
+}
+
 impl Role {
     pub fn to_str(&self) -> &'static str {
         match *self {
+            Role::ReadOnly => "read-only",
+            Role::Translator => "translator",
+            Role::Reviewer => "reviewer",
+            Role::Developer => "developer",
             Role::User => "user",
             Role::Admin => "admin",
         }
@@ -29,6 +64,10 @@ impl Role {
 
     pub fn from_str<S: AsRef<str>>(raw: S) -> Result<Role> {
         let role = match raw.as_ref() {
+            "read-only" => Role::ReadOnly,
+            "translator" => Role::Translator,
+            "reviewer" => Role::Reviewer,
+            "developer" => Role::Developer,
             "user" => Role::User,
             "admin" => Role::Admin,
             _ => {
@@ -37,6 +76,20 @@ impl Role {
         };
         Ok(role)
     }
+
+    pub fn has_permission(&self, perm: Permission) -> bool {
+        use self::Permission::*;
+
+        match (*self, perm) {
+            (Role::Admin, _) => true,
+            (_, Read) | (_, Export) => true,
+            (Role::Translator, Translate) => true,
+            (Role::Reviewer, Translate) | (Role::Reviewer, Review) => true,
+            (Role::Developer, ManageKeys) => true,
+            (Role::User, Translate) | (Role::User, ManageKeys) => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Insertable, Queryable, AsChangeset,
@@ -46,6 +99,8 @@ pub struct User {
     pub id: String,
     pub username: String,
     pub role: String,
+    /// Omitted from serialized output when cleared, e.g. for exports.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub password_hash: String,
     pub created_at: i64,
 }
@@ -71,28 +126,47 @@ impl User {
         }
     }
 
+    /// Check if the role of the user grants a permission.
+    /// Users with an unknown role have no permissions.
+    pub fn has_permission(&self, perm: Permission) -> bool {
+        Role::from_str(&self.role)
+            .map(|r| r.has_permission(perm))
+            .unwrap_or(false)
+    }
+
   pub fn verify_password<S: AsRef<str>>(&self, pw: S) -> bool {
     use ::ring_pwhash::scrypt::{scrypt_check};
     scrypt_check(pw.as_ref(), &self.password_hash).unwrap_or(false)
   }
 
-    pub fn build_session_token(&self) -> Result<ApiToken> {
+    /// Build a session token signed with `secret` that is valid for `lifetime` seconds.
+    pub fn build_session_token(&self, secret: &str, lifetime: i64) -> Result<ApiToken> {
         use simple_jwt::{encode, Claim, Algorithm};
 
+        let now = Utc::now().timestamp();
+        let expires_at = now + lifetime;
+        // Unique id, so that multiple logins within the same second yield different tokens.
+        let jti = ::uuid::Uuid::new_v4().to_string();
+
         let mut claim = Claim::default();
         claim.set_iss("translator");
-        claim.set_iat(Utc::now().timestamp() as u64);
+        claim.set_jti(jti.as_str());
+        claim.set_iat(now as u64);
+        claim.set_exp(expires_at as u64);
         claim.set_payload_field("username", &self.username);
 
-        let jwt = encode(&claim, "secret", Algorithm::HS256)
+        let jwt = encode(&claim, secret, Algorithm::HS256)
             .chain_err(|| "Could not create jwt token")?;
 
         Ok(ApiToken{
             token: jwt,
             kind: TokenKind::Session.to_str().to_string(),
-            created_at: Utc::now().timestamp(),
-            expires_at: None,
+            created_at: now,
+            expires_at: Some(expires_at),
             created_by: Some(self.id.clone()),
+            name: None,
+            scopes: None,
+            id: jti,
         })
     }
 }
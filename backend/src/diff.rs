@@ -0,0 +1,232 @@
+//! Word level differences between two values.
+//!
+//! Values are split into words and the whitespace between them, and compared
+//! by their longest common subsequence.
+
+use ::db::schema::PluralForm;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChangeKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl ChangeKind {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            ChangeKind::Equal => "equal",
+            ChangeKind::Insert => "insert",
+            ChangeKind::Delete => "delete",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub text: String,
+}
+
+/// The changes to one value of a translation. Plural translations have one
+/// for each category.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ValueDiff {
+    /// The plural category, None for translations of other keys.
+    pub category: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+/// Split a value into words and whitespace, so that joining the tokens gives
+/// back the value.
+fn tokens(value: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut space = None;
+    for (index, c) in value.char_indices() {
+        let is_space = c.is_whitespace();
+        if space.is_some() && space != Some(is_space) {
+            out.push(&value[start..index]);
+            start = index;
+        }
+        space = Some(is_space);
+    }
+    if start < value.len() {
+        out.push(&value[start..]);
+    }
+    out
+}
+
+fn push(changes: &mut Vec<Change>, kind: ChangeKind, text: &str) {
+    if let Some(last) = changes.last_mut() {
+        if last.kind == kind {
+            last.text.push_str(text);
+            return;
+        }
+    }
+    changes.push(Change {
+        kind,
+        text: text.to_string(),
+    });
+}
+
+/// The changes that turn `old` into `new`. Adjacent changes of the same kind
+/// are merged.
+pub fn diff(old: &str, new: &str) -> Vec<Change> {
+    let a = tokens(old);
+    let b = tokens(new);
+
+    // lengths[i][j] is the length of the common subsequence of a[i..] and b[j..].
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            push(&mut changes, ChangeKind::Equal, a[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            push(&mut changes, ChangeKind::Delete, a[i]);
+            i += 1;
+        } else {
+            push(&mut changes, ChangeKind::Insert, b[j]);
+            j += 1;
+        }
+    }
+    for token in &a[i..] {
+        push(&mut changes, ChangeKind::Delete, token);
+    }
+    for token in &b[j..] {
+        push(&mut changes, ChangeKind::Insert, token);
+    }
+    changes
+}
+
+/// The changes to each plural category that either version has a form for,
+/// in the canonical order of the categories. Missing forms count as empty.
+pub fn plural_diff(old: &[PluralForm], new: &[PluralForm]) -> Vec<ValueDiff> {
+    let value = |forms: &[PluralForm], category: &str| forms.iter()
+        .find(|f| f.category == category)
+        .map(|f| f.value.clone())
+        .unwrap_or(String::new());
+
+    ::plurals::CATEGORIES.iter()
+        .filter(|c| old.iter().chain(new.iter()).any(|f| f.category == **c))
+        .map(|c| ValueDiff {
+            category: Some(c.to_string()),
+            changes: diff(&value(old, *c), &value(new, *c)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(kind: ChangeKind, text: &str) -> Change {
+        Change {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    fn form(category: &str, value: &str) -> PluralForm {
+        PluralForm {
+            translation_id: "t".to_string(),
+            category: category.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_words() {
+        assert_eq!(diff("the red car", "the blue car"), vec![
+            change(ChangeKind::Equal, "the "),
+            change(ChangeKind::Delete, "red"),
+            change(ChangeKind::Insert, "blue"),
+            change(ChangeKind::Equal, " car"),
+        ]);
+        assert_eq!(diff("same", "same"), vec![change(ChangeKind::Equal, "same")]);
+    }
+
+    #[test]
+    fn diff_empty_values() {
+        assert_eq!(diff("", "new value"), vec![change(ChangeKind::Insert, "new value")]);
+        assert_eq!(diff("old value", ""), vec![change(ChangeKind::Delete, "old value")]);
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_whitespace() {
+        assert_eq!(diff("a b", "a  b"), vec![
+            change(ChangeKind::Equal, "a"),
+            change(ChangeKind::Delete, " "),
+            change(ChangeKind::Insert, "  "),
+            change(ChangeKind::Equal, "b"),
+        ]);
+        assert_eq!(diff("a", "a\n"), vec![
+            change(ChangeKind::Equal, "a"),
+            change(ChangeKind::Insert, "\n"),
+        ]);
+    }
+
+    #[test]
+    fn merge_adjacent_changes() {
+        assert_eq!(diff("a b c", "a"), vec![
+            change(ChangeKind::Equal, "a"),
+            change(ChangeKind::Delete, " b c"),
+        ]);
+        assert_eq!(diff("a b", "a b c d"), vec![
+            change(ChangeKind::Equal, "a b"),
+            change(ChangeKind::Insert, " c d"),
+        ]);
+    }
+
+    #[test]
+    fn diff_multi_byte_characters() {
+        assert_eq!(diff("grüße aus Köln", "grüße aus München"), vec![
+            change(ChangeKind::Equal, "grüße aus "),
+            change(ChangeKind::Delete, "Köln"),
+            change(ChangeKind::Insert, "München"),
+        ]);
+        assert_eq!(diff("日本\u{3000}語", "日本\u{3000}語 です"), vec![
+            change(ChangeKind::Equal, "日本\u{3000}語"),
+            change(ChangeKind::Insert, " です"),
+        ]);
+    }
+
+    #[test]
+    fn diff_plural_forms_by_category() {
+        let old = vec![form("other", "{n} files"), form("one", "one file")];
+        let new = vec![form("one", "one file"), form("few", "{n} files"), form("other", "{n} documents")];
+        assert_eq!(plural_diff(&old, &new), vec![
+            ValueDiff {
+                category: Some("one".to_string()),
+                changes: vec![change(ChangeKind::Equal, "one file")],
+            },
+            ValueDiff {
+                category: Some("few".to_string()),
+                changes: vec![change(ChangeKind::Insert, "{n} files")],
+            },
+            ValueDiff {
+                category: Some("other".to_string()),
+                changes: vec![
+                    change(ChangeKind::Equal, "{n} "),
+                    change(ChangeKind::Delete, "files"),
+                    change(ChangeKind::Insert, "documents"),
+                ],
+            },
+        ]);
+        assert!(plural_diff(&[], &[]).is_empty());
+    }
+}
@@ -9,8 +9,38 @@ error_chain!{
 
     errors {
         UnknownUser { }
+        UnknownProject { }
+        UnknownLanguage { }
+        UnknownKey { }
+        UnknownTranslation { }
         InvalidPassword { }
         InvalidRole { }
         PermissionDenied {}
+        InvalidToken {
+            description("Invalid authentication token")
+        }
+        TokenExpired {
+            description("Authentication token has expired")
+        }
+        IncompatibleExport(version: u64) {
+            description("Incompatible export version")
+            display("Incompatible export version {}: only version {} can be restored", version, ::db::EXPORT_VERSION)
+        }
+        InvalidScope(scope: String) {
+            description("Invalid token scope")
+            display("Invalid token scope: {}", scope)
+        }
+        InvalidMessage(error: ::message_format::ParseError) {
+            description("Invalid message")
+            display("Invalid message: {}", error)
+        }
+        TranslationConflict(current: Option<::db::schema::Translation>, forms: Vec<::db::schema::PluralForm>, author: Option<String>) {
+            description("Translation was changed in the meantime")
+            display("Translation was changed in the meantime{}",
+                    match *current {
+                        Some(ref t) => format!(": version {} by {}", t.version, author.as_ref().map(|a| a.as_str()).unwrap_or("unknown")),
+                        None => ": it was deleted".to_string(),
+                    })
+        }
     }
 }
@@ -0,0 +1,95 @@
+//! Android `values-<code>/strings.xml` resources.
+
+use super::mobile::{MobileEntry, MobileValue};
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            // Leading @ and ? would be read as resource references.
+            '@' | '?' if index == 0 => {
+                out.push('\\');
+                out.push(c);
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `--` is not allowed inside of XML comments.
+fn comment(value: &str) -> String {
+    value.replace("--", "- -")
+}
+
+pub fn write_resources(entries: &[MobileEntry]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<!-- Generated by translator. Do not edit by hand! -->\n");
+    out.push_str("<resources>\n");
+
+    for entry in entries {
+        if let Some(ref description) = entry.description {
+            out.push_str(&format!("    <!-- {} -->\n", comment(description)));
+        }
+        match entry.value {
+            MobileValue::Single(ref value) => {
+                out.push_str(&format!("    <string name=\"{}\">{}</string>\n", entry.name, escape(value)));
+            },
+            MobileValue::Plural(ref forms) => {
+                out.push_str(&format!("    <plurals name=\"{}\">\n", entry.name));
+                for &(category, ref value) in forms {
+                    out.push_str(&format!("        <item quantity=\"{}\">{}</item>\n", category, escape(value)));
+                }
+                out.push_str("    </plurals>\n");
+            },
+        }
+    }
+
+    out.push_str("</resources>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_values() {
+        assert_eq!(escape("@string/name"), "\\@string/name");
+        assert_eq!(escape("?attr"), "\\?attr");
+        assert_eq!(escape("mail@example.com?"), "mail@example.com?");
+        assert_eq!(escape("It's \"quoted\""), "It\\'s \\\"quoted\\\"");
+        assert_eq!(escape("one\ntwo\tthree \\"), "one\\ntwo\\tthree \\\\");
+        assert_eq!(escape("<b>&</b>"), "&lt;b&gt;&amp;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn write_strings_and_plurals() {
+        let entries = vec![
+            MobileEntry {
+                name: "files".to_string(),
+                description: Some("A -- count".to_string()),
+                value: MobileValue::Plural(vec![("one", "One file".to_string()), ("other", "%d files".to_string())]),
+            },
+            MobileEntry {
+                name: "title".to_string(),
+                description: None,
+                value: MobileValue::Single("@Home".to_string()),
+            },
+        ];
+        let xml = write_resources(&entries);
+        assert!(xml.contains("    <!-- A - - count -->\n    <plurals name=\"files\">\n"));
+        assert!(xml.contains("        <item quantity=\"one\">One file</item>\n"));
+        assert!(xml.contains("        <item quantity=\"other\">%d files</item>\n    </plurals>\n"));
+        assert!(xml.contains("    <string name=\"title\">\\@Home</string>\n"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Zip archives with the exports of all languages of a project.
+//!
+//! File paths are built from templates like `locales/{code}/messages.json`.
+//! Supported placeholders:
+//!
+//! * `{code}`: the language code, e.g. `de-AT`
+//! * `{android_code}`: the Android resource qualifier, e.g. `de-rAT`
+
+use std::io::{Cursor, Write};
+
+use zip::{ZipWriter, CompressionMethod};
+use zip::write::FileOptions;
+
+use ::error::*;
+
+pub struct BundleFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// The Android resource qualifier for a language code.
+pub fn android_code(code: &str) -> String {
+    let parts: Vec<&str> = code.split(|c| c == '-' || c == '_').collect();
+    match parts.len() {
+        1 => parts[0].to_string(),
+        2 if parts[1].len() == 2 => format!("{}-r{}", parts[0], parts[1].to_uppercase()),
+        _ => format!("b+{}", parts.join("+")),
+    }
+}
+
+/// Build a file path from a template.
+/// Paths must be relative and must not leave the archive root.
+pub fn expand_path(template: &str, code: &str) -> Result<String> {
+    let path = template
+        .replace("{code}", code)
+        .replace("{android_code}", &android_code(code));
+
+    if path.is_empty() || path.starts_with('/') || path.contains('\\') {
+        return Err(format!("Invalid bundle path: {}", path).into());
+    }
+    if path.split('/').any(|segment| segment == "" || segment == "." || segment == "..") {
+        return Err(format!("Invalid bundle path: {}", path).into());
+    }
+    Ok(path)
+}
+
+/// Write the files into a zip archive. The archive is kept in memory.
+pub fn write_zip(files: &[BundleFile]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for file in files {
+        zip.start_file(file.path.as_str(), options)
+           .chain_err(|| format!("Could not add {} to the bundle", file.path))?;
+        zip.write_all(file.content.as_bytes())
+           .chain_err(|| format!("Could not add {} to the bundle", file.path))?;
+    }
+
+    let cursor = zip.finish().chain_err(|| "Could not write the bundle")?;
+    Ok(cursor.into_inner())
+}
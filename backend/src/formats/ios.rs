@@ -0,0 +1,115 @@
+//! iOS `<code>.lproj/Localizable.strings` and `Localizable.stringsdict`
+//! resources.
+//!
+//! Plurals can't be expressed in `.strings` files, so they are only written
+//! to the `.stringsdict` file.
+
+use super::mobile::{MobileEntry, MobileValue};
+
+fn escape_strings(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn write_strings(entries: &[MobileEntry]) -> String {
+    let mut out = String::new();
+    out.push_str("/* Generated by translator. Do not edit by hand! */\n");
+
+    for entry in entries {
+        let value = match entry.value {
+            MobileValue::Single(ref v) => v,
+            MobileValue::Plural(_) => continue,
+        };
+        out.push('\n');
+        if let Some(ref description) = entry.description {
+            out.push_str(&format!("/* {} */\n", description.replace("*/", "* /")));
+        }
+        out.push_str(&format!("\"{}\" = \"{}\";\n", entry.name, escape_strings(value)));
+    }
+
+    out
+}
+
+/// Plurals are exposed through a single `%d` format argument.
+pub fn write_stringsdict(entries: &[MobileEntry]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n");
+    out.push_str("<!-- Generated by translator. Do not edit by hand! -->\n");
+    out.push_str("<plist version=\"1.0\">\n<dict>\n");
+
+    for entry in entries {
+        let forms = match entry.value {
+            MobileValue::Plural(ref forms) => forms,
+            MobileValue::Single(_) => continue,
+        };
+        out.push_str(&format!("    <key>{}</key>\n", entry.name));
+        out.push_str("    <dict>\n");
+        out.push_str("        <key>NSStringLocalizedFormatKey</key>\n");
+        out.push_str("        <string>%#@value@</string>\n");
+        out.push_str("        <key>value</key>\n");
+        out.push_str("        <dict>\n");
+        out.push_str("            <key>NSStringFormatSpecTypeKey</key>\n");
+        out.push_str("            <string>NSStringPluralRuleType</string>\n");
+        out.push_str("            <key>NSStringFormatValueTypeKey</key>\n");
+        out.push_str("            <string>d</string>\n");
+        for &(category, ref value) in forms {
+            out.push_str(&format!("            <key>{}</key>\n", category));
+            out.push_str(&format!("            <string>{}</string>\n", escape_xml(value)));
+        }
+        out.push_str("        </dict>\n");
+        out.push_str("    </dict>\n");
+    }
+
+    out.push_str("</dict>\n</plist>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<MobileEntry> {
+        vec![
+            MobileEntry {
+                name: "files".to_string(),
+                description: None,
+                value: MobileValue::Plural(vec![("one", "One <file>".to_string()), ("other", "%d files & more".to_string())]),
+            },
+            MobileEntry {
+                name: "title".to_string(),
+                description: Some("Shown */ on top".to_string()),
+                value: MobileValue::Single("Say \"hi\"\n\\o/".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn write_strings_skips_plurals() {
+        assert_eq!(write_strings(&entries()),
+                   "/* Generated by translator. Do not edit by hand! */\n\n/* Shown * / on top */\n\"title\" = \"Say \\\"hi\\\"\\n\\\\o/\";\n");
+    }
+
+    #[test]
+    fn write_stringsdict_plurals() {
+        let plist = write_stringsdict(&entries());
+        assert!(plist.contains("    <key>files</key>\n"));
+        assert!(plist.contains("            <key>one</key>\n            <string>One &lt;file&gt;</string>\n"));
+        assert!(plist.contains("            <key>other</key>\n            <string>%d files &amp; more</string>\n"));
+        assert!(!plist.contains("title"));
+    }
+}
@@ -0,0 +1,79 @@
+use serde_json::{self, Value};
+
+use ::error::*;
+use ::repo::TranslationsExport;
+
+/// Parse translations from either a flat `{ "a.b": "value" }` map or a
+/// nested `{ "a": { "b": "value" } }` tree.
+pub fn parse_translations(content: &str) -> Result<TranslationsExport> {
+    let data: Value = serde_json::from_str(content)?;
+    if !data.is_object() {
+        return Err("Invalid JSON translations: expected an object".into());
+    }
+
+    let mut translations = TranslationsExport::new();
+    flatten(&data, "", &mut translations)?;
+    Ok(translations)
+}
+
+fn flatten(value: &Value, prefix: &str, out: &mut TranslationsExport) -> Result<()> {
+    match *value {
+        Value::Object(ref map) => {
+            for (name, nested) in map.iter() {
+                let key = if prefix == "" {
+                    name.clone()
+                } else {
+                    format!("{}.{}", prefix, name)
+                };
+                flatten(nested, &key, out)?;
+            }
+        },
+        Value::String(ref s) => {
+            out.insert(prefix.to_string(), s.clone());
+        },
+        // Missing translations.
+        Value::Null => {},
+        _ => {
+            return Err(format!("Invalid value for key {}: expected a string", prefix).into());
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<(String, String)> {
+        parse_translations(content).unwrap().into_iter().collect()
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn parse_flat_and_nested() {
+        let expected = vec![pair("a.b", "B"), pair("a.c.d", "D"), pair("e", "E")];
+        assert_eq!(parse(r#"{"a.b": "B", "a.c.d": "D", "e": "E"}"#), expected);
+        assert_eq!(parse(r#"{"a": {"b": "B", "c": {"d": "D"}}, "e": "E"}"#), expected);
+        // Both styles can be mixed.
+        assert_eq!(parse(r#"{"a": {"b": "B", "c.d": "D"}, "e": "E"}"#), expected);
+        assert!(parse("{}").is_empty());
+    }
+
+    #[test]
+    fn skip_nulls() {
+        assert_eq!(parse(r#"{"a": null, "b": {"c": null, "d": "D"}}"#), vec![pair("b.d", "D")]);
+    }
+
+    #[test]
+    fn reject_other_values() {
+        let error = |content: &str| parse_translations(content).unwrap_err().to_string();
+        assert_eq!(error(r#"{"a": {"b": 1}}"#), "Invalid value for key a.b: expected a string");
+        assert_eq!(error(r#"{"a": true}"#), "Invalid value for key a: expected a string");
+        assert_eq!(error(r#"{"a": ["x"]}"#), "Invalid value for key a: expected a string");
+        assert_eq!(error(r#"["x"]"#), "Invalid JSON translations: expected an object");
+        assert!(parse_translations("{").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Shared helpers for the Android and iOS resource formats.
+//!
+//! Mobile platforms need resource names without dots, so keys are mangled by
+//! replacing every character outside of `[A-Za-z0-9_]` with `_`:
+//! `a.b_c` becomes `a_b_c`.
+//!
+//! Plural keys carry their values per CLDR category. Sibling keys named
+//! after the categories are treated as plurals too:
+//! `inbox.messages.one` and `inbox.messages.other` form the plural
+//! `inbox_messages`.
+
+use std::collections::BTreeMap;
+
+use ::error::*;
+use super::check_unique;
+
+pub fn mangle_key(key: &str) -> String {
+    let mut name: String = key.chars()
+        .map(|c| match c {
+            'a'...'z' | 'A'...'Z' | '0'...'9' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    // Resource names must not start with a digit.
+    if name.chars().next().map(|c| c >= '0' && c <= '9').unwrap_or(false) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum MobileValue {
+    Single(String),
+    /// Values by CLDR category, in canonical order.
+    Plural(Vec<(&'static str, String)>),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MobileEntry {
+    /// The mangled resource name.
+    pub name: String,
+    pub description: Option<String>,
+    pub value: MobileValue,
+}
+
+/// Build the resources for a set of `(key, description, value)` triples.
+///
+/// Sibling keys named after plural categories are grouped into a plural when
+/// an `other` value exists. Fails if two keys map onto the same name.
+pub fn build_entries(values: Vec<(String, Option<String>, MobileValue)>) -> Result<Vec<MobileEntry>> {
+    // Plural candidates by their parent key.
+    let mut plurals: BTreeMap<String, Vec<(&'static str, String, Option<String>, String)>> = BTreeMap::new();
+    let mut singles = Vec::new();
+    let mut explicit = Vec::new();
+
+    for (key, description, value) in values {
+        let value = match value {
+            MobileValue::Single(v) => v,
+            MobileValue::Plural(forms) => {
+                explicit.push((key, description, forms));
+                continue;
+            },
+        };
+        let split = key.rfind('.').and_then(|index| {
+            ::plurals::category(&key[index + 1..]).map(|c| (key[..index].to_string(), c))
+        });
+        match split {
+            Some((parent, category)) => {
+                plurals.entry(parent).or_insert(Vec::new()).push((category, key, description, value));
+            },
+            None => singles.push((key, description, value)),
+        }
+    }
+
+    // Entries and the key they were built from.
+    let mut entries: Vec<(String, MobileEntry)> = Vec::new();
+
+    for (key, description, value) in singles {
+        let name = mangle_key(&key);
+        entries.push((key, MobileEntry {
+            name,
+            description,
+            value: MobileValue::Single(value),
+        }));
+    }
+
+    for (key, description, forms) in explicit {
+        let name = mangle_key(&key);
+        entries.push((key, MobileEntry {
+            name,
+            description,
+            value: MobileValue::Plural(forms),
+        }));
+    }
+
+    for (parent, mut forms) in plurals {
+        if !forms.iter().any(|f| f.0 == "other") {
+            // Not a plural after all.
+            for (_, key, description, value) in forms {
+                let name = mangle_key(&key);
+                entries.push((key, MobileEntry {
+                    name,
+                    description,
+                    value: MobileValue::Single(value),
+                }));
+            }
+            continue;
+        }
+
+        forms.sort_by_key(|f| ::plurals::CATEGORIES.iter().position(|c| *c == f.0));
+        let description = forms.iter().filter_map(|f| f.2.clone()).next();
+        let name = mangle_key(&parent);
+        entries.push((parent, MobileEntry {
+            name,
+            description,
+            value: MobileValue::Plural(forms.into_iter().map(|f| (f.0, f.3)).collect()),
+        }));
+    }
+
+    let names: Vec<(String, String)> = entries.iter()
+        .map(|&(ref key, ref entry)| (key.clone(), entry.name.clone()))
+        .collect();
+    check_unique(&names, "key")?;
+
+    entries.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(key: &str, value: &str) -> (String, Option<String>, MobileValue) {
+        (key.to_string(), None, MobileValue::Single(value.to_string()))
+    }
+
+    #[test]
+    fn mangle_keys() {
+        assert_eq!(mangle_key("a.b_c"), "a_b_c");
+        assert_eq!(mangle_key("menu.file-open"), "menu_file_open");
+        assert_eq!(mangle_key("größe"), "gr__e");
+        assert_eq!(mangle_key("404.title"), "_404_title");
+    }
+
+    #[test]
+    fn group_plural_siblings() {
+        let entries = build_entries(vec![
+            single("inbox.messages.other", "{n} messages"),
+            ("inbox.messages.one".to_string(), Some("Unread messages".to_string()), MobileValue::Single("One message".to_string())),
+            single("inbox.title", "Inbox"),
+        ]).unwrap();
+        assert_eq!(entries, vec![
+            MobileEntry {
+                name: "inbox_messages".to_string(),
+                description: Some("Unread messages".to_string()),
+                value: MobileValue::Plural(vec![
+                    ("one", "One message".to_string()),
+                    ("other", "{n} messages".to_string()),
+                ]),
+            },
+            MobileEntry {
+                name: "inbox_title".to_string(),
+                description: None,
+                value: MobileValue::Single("Inbox".to_string()),
+            },
+        ]);
+    }
+
+    #[test]
+    fn siblings_without_other_stay_single() {
+        let entries = build_entries(vec![
+            single("size.one", "1"),
+            single("size.few", "2"),
+        ]).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["size_few", "size_one"]);
+        assert!(entries.iter().all(|e| match e.value { MobileValue::Single(_) => true, _ => false }));
+    }
+
+    #[test]
+    fn explicit_plurals() {
+        let forms = vec![("one", "a".to_string()), ("other", "b".to_string())];
+        let entries = build_entries(vec![
+            ("a.files".to_string(), None, MobileValue::Plural(forms.clone())),
+        ]).unwrap();
+        assert_eq!(entries[0].name, "a_files");
+        assert_eq!(entries[0].value, MobileValue::Plural(forms));
+    }
+
+    #[test]
+    fn reject_mangle_collisions() {
+        let e = build_entries(vec![single("a.b", "x"), single("a_b", "y")]).unwrap_err();
+        assert_eq!(e.to_string(), "The keys a.b and a_b both map to the name a_b");
+
+        // Grouped plurals collide with their mangled parent too.
+        let e = build_entries(vec![
+            single("a_b", "x"),
+            single("a.b.one", "y"),
+            single("a.b.other", "z"),
+        ]).unwrap_err();
+        assert_eq!(e.to_string(), "The keys a_b and a.b both map to the name a_b");
+    }
+}
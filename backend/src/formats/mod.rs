@@ -0,0 +1,31 @@
+//! Parsing and serialization of the supported translation file formats.
+
+pub mod android;
+pub mod bundle;
+pub mod ios;
+pub mod json;
+pub mod mobile;
+pub mod params;
+pub mod po;
+pub mod rust;
+pub mod typescript;
+pub mod xliff;
+
+use std::collections::BTreeMap;
+
+use ::error::*;
+
+/// Check that no two `(original, name)` pairs share a generated name, for
+/// formats that derive identifiers from keys or languages.
+pub fn check_unique(names: &[(String, String)], what: &str) -> Result<()> {
+    let mut seen: BTreeMap<&str, &str> = BTreeMap::new();
+    for &(ref original, ref name) in names {
+        if name.is_empty() {
+            return Err(format!("Can't generate a name for the {} {}", what, original).into());
+        }
+        if let Some(other) = seen.insert(name.as_str(), original.as_str()) {
+            return Err(format!("The {}s {} and {} both map to the name {}", what, other, original, name).into());
+        }
+    }
+    Ok(())
+}
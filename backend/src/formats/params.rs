@@ -0,0 +1,63 @@
+//! Parameters of translated values, used to generate typed code.
+//!
+//! Parameters are the arguments of MessageFormat values: `{name}`, or
+//! `{name, type, ...}` for formatted arguments like
+//! `{count, plural, one {# item} other {# items}}`.
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ParamKind {
+    /// Plain arguments accept strings and numbers.
+    Any,
+    /// `number`, `plural` and `selectordinal` arguments.
+    Number,
+    /// `date` and `time` arguments.
+    Date,
+    /// `select` arguments.
+    Text,
+}
+
+impl ParamKind {
+    fn from_type(kind: &str) -> Self {
+        match kind {
+            "number" | "plural" | "selectordinal" => ParamKind::Number,
+            "date" | "time" => ParamKind::Date,
+            "select" => ParamKind::Text,
+            _ => ParamKind::Any,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamKind,
+}
+
+/// Extract the parameters of a value, in order of first use.
+/// A parameter used with different types keeps the most specific one.
+/// Values that are not valid messages have no parameters.
+pub fn extract_params(value: &str) -> Vec<Param> {
+    let message = match ::message_format::parse(value) {
+        Ok(m) => m,
+        Err(_) => {
+            return Vec::new();
+        },
+    };
+
+    let mut params: Vec<Param> = Vec::new();
+    for arg in ::message_format::arguments(&message) {
+        let kind = arg.format.kind().map(ParamKind::from_type).unwrap_or(ParamKind::Any);
+        match params.iter().position(|p| p.name == arg.name) {
+            Some(i) => {
+                if params[i].kind == ParamKind::Any {
+                    params[i].kind = kind;
+                }
+            },
+            None => params.push(Param {
+                name: arg.name.clone(),
+                kind,
+            }),
+        }
+    }
+    params
+}
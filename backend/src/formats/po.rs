@@ -0,0 +1,424 @@
+//! Gettext PO and POT catalogs.
+//!
+//! Keys are mapped onto gettext entries by using the key prefix as `msgctxt`
+//! and the last key segment as `msgid`, so `a.b.c` becomes
+//! `msgctxt "a.b"` / `msgid "c"`.
+//!
+//! Plural keys repeat the `msgid` as `msgid_plural`, with a `msgstr[n]` for
+//! every gettext form of the language. Gettext only counts integers, so
+//! categories that only cover fractions have no form.
+
+use ::error::*;
+use ::db::schema::PluralValue;
+use ::repo::{TranslationsImport, ImportValue};
+
+pub struct PoEntry {
+    pub key: String,
+    /// Emitted as a comment for translators.
+    pub description: Option<String>,
+    pub value: Option<String>,
+    /// The values of plural keys by category, in the order of the `Plural-Forms`
+    /// header. None for other keys.
+    pub plurals: Option<Vec<String>>,
+}
+
+/// The gettext `Plural-Forms` header for a language code.
+pub fn plural_forms(code: &str) -> &'static str {
+    ::plurals::rule(code).gettext
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write a keyword with a string, split over multiple lines after newlines.
+fn write_string(out: &mut String, keyword: &str, value: &str) {
+    let lines: Vec<&str> = value.split_terminator('\n').collect();
+    if lines.len() < 2 {
+        out.push_str(&format!("{} \"{}\"\n", keyword, escape(value)));
+        return;
+    }
+
+    out.push_str(&format!("{} \"\"\n", keyword));
+    let last = lines.len() - 1;
+    for (index, line) in lines.iter().enumerate() {
+        let newline = index < last || value.ends_with('\n');
+        out.push_str(&format!("\"{}{}\"\n", escape(line), if newline { "\\n" } else { "" }));
+    }
+}
+
+/// Render a catalog.
+/// Without a language, a POT template with empty translations is produced.
+pub fn write_catalog(project: &str, language: Option<&str>, entries: &[PoEntry]) -> String {
+    let mut out = String::new();
+
+    out.push_str("# Generated by translator.\n");
+    if language.is_none() {
+        out.push_str("#, fuzzy\n");
+    }
+    out.push_str("msgid \"\"\nmsgstr \"\"\n");
+    out.push_str(&format!("\"Project-Id-Version: {}\\n\"\n", escape(project)));
+    out.push_str(&format!("\"Language: {}\\n\"\n", language.map(|l| l.replace('-', "_")).unwrap_or(String::new())));
+    out.push_str("\"MIME-Version: 1.0\\n\"\n");
+    out.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    out.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    out.push_str(&format!("\"Plural-Forms: {}\\n\"\n",
+        language.map(plural_forms).unwrap_or("nplurals=INTEGER; plural=EXPRESSION;")));
+
+    for entry in entries {
+        out.push('\n');
+        if let Some(ref description) = entry.description {
+            for line in description.lines() {
+                out.push_str(&format!("#. {}\n", line));
+            }
+        }
+        out.push_str(&format!("#: {}\n", entry.key));
+
+        match entry.key.rfind('.') {
+            Some(index) => {
+                write_string(&mut out, "msgctxt", &entry.key[..index]);
+                write_string(&mut out, "msgid", &entry.key[index + 1..]);
+            },
+            None => {
+                write_string(&mut out, "msgid", &entry.key);
+            },
+        }
+        let msgid = entry.key.rsplit('.').next().unwrap_or("");
+
+        if let Some(ref plurals) = entry.plurals {
+            write_string(&mut out, "msgid_plural", msgid);
+            let count = match language {
+                Some(code) => ::plurals::rule(code).gettext_categories.len(),
+                None => 2,
+            };
+            for index in 0..count {
+                let value = if language.is_some() { plurals.get(index) } else { None };
+                write_string(&mut out, &format!("msgstr[{}]", index), value.map(|v| v.as_str()).unwrap_or(""));
+            }
+            continue;
+        }
+
+        let value = if language.is_some() { entry.value.as_ref() } else { None };
+        write_string(&mut out, "msgstr", value.map(|v| v.as_str()).unwrap_or(""));
+    }
+
+    out
+}
+
+fn unescape(raw: &str, line: usize) -> Result<String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            _ => {
+                return Err(format!("Invalid escape sequence on line {}", line).into());
+            },
+        }
+    }
+    Ok(out)
+}
+
+/// Parse a quoted string.
+fn parse_string(raw: &str, line: usize) -> Result<String> {
+    let raw = raw.trim();
+    if raw.len() < 2 || !raw.starts_with('"') || !raw.ends_with('"') {
+        return Err(format!("Expected a quoted string on line {}", line).into());
+    }
+    unescape(&raw[1..raw.len() - 1], line)
+}
+
+#[derive(Default)]
+struct RawEntry {
+    fuzzy: bool,
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgid_plural: Option<String>,
+    msgstr: Vec<String>,
+}
+
+impl RawEntry {
+    fn is_empty(&self) -> bool {
+        self.msgid.is_none() && self.msgctxt.is_none()
+    }
+
+    fn key(&self) -> Option<String> {
+        let msgid = match self.msgid {
+            Some(ref id) if id != "" => id,
+            // The header.
+            _ => {
+                return None;
+            },
+        };
+        Some(match self.msgctxt {
+            Some(ref ctx) => format!("{}.{}", ctx, msgid),
+            None => msgid.clone(),
+        })
+    }
+}
+
+/// Parse the translations of a PO catalog for the language `code`.
+/// The `msgstr[n]` values of plural entries are taken to follow the order of
+/// the gettext categories of the language.
+/// Untranslated and fuzzy entries are skipped.
+pub fn parse_catalog(content: &str, code: &str) -> Result<TranslationsImport> {
+    let mut entries = Vec::new();
+    let mut entry = RawEntry::default();
+    // The string the last keyword applies to, for continuation lines.
+    let mut target: Option<(&str, usize)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+
+        if line == "" {
+            continue;
+        }
+        if line.starts_with('#') {
+            // Comments start a new entry.
+            if target.is_some() {
+                entries.push(::std::mem::replace(&mut entry, RawEntry::default()));
+                target = None;
+            }
+            if line.starts_with("#,") && line.contains("fuzzy") {
+                entry.fuzzy = true;
+            }
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let value = parse_string(line, number)?;
+            let s = match target {
+                Some(("msgctxt", _)) => entry.msgctxt.as_mut(),
+                Some(("msgid", _)) => entry.msgid.as_mut(),
+                Some(("msgid_plural", _)) => entry.msgid_plural.as_mut(),
+                Some(("msgstr", i)) => entry.msgstr.get_mut(i),
+                _ => None,
+            };
+            match s {
+                Some(s) => s.push_str(&value),
+                None => {
+                    return Err(format!("Unexpected string on line {}", number).into());
+                },
+            }
+            continue;
+        }
+
+        let split = line.find(|c: char| c.is_whitespace()).unwrap_or(line.len());
+        let (keyword, rest) = line.split_at(split);
+        let value = parse_string(rest, number)?;
+
+        // A msgctxt or msgid after a msgstr starts a new entry.
+        let starts_entry = keyword == "msgctxt" || keyword == "msgid";
+        if starts_entry && entry.msgstr.len() > 0 {
+            entries.push(::std::mem::replace(&mut entry, RawEntry::default()));
+        }
+
+        target = match keyword {
+            "msgctxt" => {
+                entry.msgctxt = Some(value);
+                Some(("msgctxt", 0))
+            },
+            "msgid" => {
+                entry.msgid = Some(value);
+                Some(("msgid", 0))
+            },
+            "msgid_plural" => {
+                entry.msgid_plural = Some(value);
+                Some(("msgid_plural", 0))
+            },
+            "msgstr" => {
+                entry.msgstr = vec![value];
+                Some(("msgstr", 0))
+            },
+            k if k.starts_with("msgstr[") && k.ends_with(']') => {
+                let i: usize = k["msgstr[".len()..k.len() - 1].parse()
+                    .map_err(|_| Error::from(format!("Invalid plural index on line {}", number)))?;
+                if i != entry.msgstr.len() {
+                    return Err(format!("Unexpected plural index on line {}", number).into());
+                }
+                entry.msgstr.push(value);
+                Some(("msgstr", i))
+            },
+            _ => {
+                return Err(format!("Unknown keyword {} on line {}", keyword, number).into());
+            },
+        };
+    }
+    if !entry.is_empty() {
+        entries.push(entry);
+    }
+
+    let categories = ::plurals::rule(code).gettext_categories;
+    let mut translations = TranslationsImport::new();
+    for entry in entries {
+        if entry.fuzzy {
+            continue;
+        }
+        let key = match entry.key() {
+            Some(k) => k,
+            None => {
+                continue;
+            },
+        };
+        if entry.msgid_plural.is_some() {
+            if entry.msgstr.iter().all(|v| v == "") {
+                continue;
+            }
+            if entry.msgstr.len() != categories.len() {
+                return Err(format!("Entry {} has {} plural forms, but {} has {}",
+                                   key, entry.msgstr.len(), code, categories.len()).into());
+            }
+            let values = categories.iter()
+                .zip(entry.msgstr.into_iter())
+                .map(|(category, value)| PluralValue {
+                    category: category.to_string(),
+                    value,
+                })
+                .collect();
+            translations.insert(key, ImportValue::Plural(values));
+            continue;
+        }
+        match entry.msgstr.into_iter().next() {
+            Some(ref value) if value != "" => {
+                translations.insert(key, ImportValue::Single(value.clone()));
+            },
+            _ => {},
+        }
+    }
+    Ok(translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single(value: &str) -> ImportValue {
+        ImportValue::Single(value.to_string())
+    }
+
+    fn plural(values: &[(&str, &str)]) -> ImportValue {
+        ImportValue::Plural(values.iter()
+            .map(|&(category, value)| PluralValue {
+                category: category.to_string(),
+                value: value.to_string(),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn parse_context_as_key_prefix() {
+        let content = "msgctxt \"a.b\"\nmsgid \"c\"\nmsgstr \"C\"\n\nmsgid \"d\"\nmsgstr \"D\"\n";
+        let translations = parse_catalog(content, "en").unwrap();
+        assert_eq!(translations.len(), 2);
+        assert_eq!(translations["a.b.c"], single("C"));
+        assert_eq!(translations["d"], single("D"));
+    }
+
+    #[test]
+    fn parse_continuation_lines() {
+        let content = "msgid \"\"\nmsgstr \"\"\n\"Language: de\\n\"\n\nmsgctxt \"a\"\nmsgid \"\"\n\"b\"\nmsgstr \"\"\n\"First line\\n\"\n\"second line\"\n";
+        let translations = parse_catalog(content, "de").unwrap();
+        assert_eq!(translations.len(), 1);
+        assert_eq!(translations["a.b"], single("First line\nsecond line"));
+    }
+
+    #[test]
+    fn parse_escapes() {
+        let content = "msgid \"a\"\nmsgstr \"\\\"quoted\\\" \\\\ \\ttab\"\n";
+        let translations = parse_catalog(content, "en").unwrap();
+        assert_eq!(translations["a"], single("\"quoted\" \\ \ttab"));
+
+        assert!(parse_catalog("msgid \"a\"\nmsgstr \"\\x\"\n", "en").is_err());
+        assert!(parse_catalog("msgid \"a\"\nmsgstr unquoted\n", "en").is_err());
+    }
+
+    #[test]
+    fn parse_plurals() {
+        let content = "msgctxt \"a\"\nmsgid \"files\"\nmsgid_plural \"files\"\nmsgstr[0] \"{n} Datei\"\nmsgstr[1] \"{n} Dateien\"\n";
+        let translations = parse_catalog(content, "de").unwrap();
+        assert_eq!(translations["a.files"], plural(&[("one", "{n} Datei"), ("other", "{n} Dateien")]));
+
+        // The number of forms has to match the language.
+        assert!(parse_catalog(content, "pl").is_err());
+        // Untranslated plurals are skipped.
+        let content = "msgid \"files\"\nmsgid_plural \"files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n";
+        assert!(parse_catalog(content, "de").unwrap().is_empty());
+        // Indices have to be consecutive.
+        let content = "msgid \"files\"\nmsgid_plural \"files\"\nmsgstr[1] \"x\"\n";
+        assert!(parse_catalog(content, "de").is_err());
+    }
+
+    #[test]
+    fn skip_fuzzy_and_untranslated_entries() {
+        let content = "#, fuzzy\nmsgid \"a\"\nmsgstr \"A\"\n\nmsgid \"b\"\nmsgstr \"\"\n\n#. A comment\nmsgid \"c\"\nmsgstr \"C\"\n";
+        let translations = parse_catalog(content, "en").unwrap();
+        assert_eq!(translations.len(), 1);
+        assert_eq!(translations["c"], single("C"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = vec![
+            PoEntry {
+                key: "a.b.c".to_string(),
+                description: Some("Two\nlines".to_string()),
+                value: Some("Line \"one\"\nline\ttwo\n".to_string()),
+                plurals: None,
+            },
+            PoEntry {
+                key: "d".to_string(),
+                description: None,
+                value: Some("Back\\slash".to_string()),
+                plurals: None,
+            },
+            PoEntry {
+                key: "e.files".to_string(),
+                description: None,
+                value: None,
+                plurals: Some(vec![
+                    "{n} plik".to_string(),
+                    "{n} pliki".to_string(),
+                    "{n} plików".to_string(),
+                ]),
+            },
+            PoEntry {
+                key: "f".to_string(),
+                description: None,
+                value: None,
+                plurals: None,
+            },
+        ];
+        let translations = parse_catalog(&write_catalog("p", Some("pl"), &entries), "pl").unwrap();
+        assert_eq!(translations.len(), 3);
+        assert_eq!(translations["a.b.c"], single("Line \"one\"\nline\ttwo\n"));
+        assert_eq!(translations["d"], single("Back\\slash"));
+        assert_eq!(translations["e.files"], plural(&[
+            ("one", "{n} plik"),
+            ("few", "{n} pliki"),
+            ("many", "{n} plików"),
+        ]));
+
+        // Templates have no translations.
+        assert!(parse_catalog(&write_catalog("p", None, &entries), "pl").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! Generated Rust modules with compiled in translations.
+//!
+//! The module contains a `Lang` and a `Key` enum, a `get(lang, key)` lookup,
+//! a `get_plural(lang, key, category)` lookup for plural keys, and a `keys`
+//! module following the key hierarchy with a constant per key.
+//! Keys whose source values only have plain `{name}` parameters also get a
+//! function with one argument per parameter.
+
+use std::collections::{BTreeMap, HashSet};
+
+use ::error::*;
+use ::repo::MutableKeyTree;
+use super::check_unique;
+use super::params::{Param, ParamKind};
+
+const KEYWORDS: &'static [&'static str] = &[
+    "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate", "do",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "offsetof", "override", "priv", "proc", "pub",
+    "pure", "ref", "return", "self", "sizeof", "static", "struct", "super", "trait", "true",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+pub struct RustLanguage {
+    pub code: String,
+    /// Translated values by key, with the other form of plural keys.
+    pub values: BTreeMap<String, String>,
+    /// The values of plural keys by category.
+    pub plurals: BTreeMap<String, Vec<(&'static str, String)>>,
+}
+
+/// A string literal. The debug representation escapes all special characters.
+fn quote(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+/// A lowercase identifier for a key segment or parameter name.
+fn ident(name: &str) -> String {
+    let mut out: String = name.chars()
+        .map(|c| match c {
+            'a'...'z' | '0'...'9' | '_' => c,
+            'A'...'Z' => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect();
+    if out.is_empty() || out.chars().next().map(|c| c >= '0' && c <= '9').unwrap_or(false) {
+        out.insert(0, '_');
+    }
+    if KEYWORDS.contains(&out.as_str()) || out == "lang" {
+        out.push('_');
+    }
+    out
+}
+
+/// A camel case name, with `_` between the segments of codes and keys.
+fn type_name(name: &str, separators: &[char]) -> String {
+    let mut name = name.split(|c| separators.contains(&c))
+        .map(|segment| {
+            let mut out = String::new();
+            for part in segment.split(|c| c == '_' || c == '-') {
+                let mut chars = part.chars();
+                if let Some(c) = chars.next() {
+                    out.extend(c.to_uppercase());
+                    out.extend(chars.filter(|c| c.is_alphanumeric()));
+                }
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("_");
+    if name.chars().next().map(|c| !c.is_alphabetic()).unwrap_or(false) {
+        name.insert(0, 'N');
+    }
+    name
+}
+
+/// Parameters usable with plain string replacement, or None if the value
+/// has formatted parameters or names that can't be told apart.
+fn plain_params(params: &[Param]) -> Option<Vec<(String, String)>> {
+    if params.is_empty() || params.iter().any(|p| p.kind != ParamKind::Any) {
+        return None;
+    }
+    let mut idents = HashSet::new();
+    let mut out = Vec::new();
+    for p in params {
+        let id = ident(&p.name);
+        if !idents.insert(id.clone()) {
+            return None;
+        }
+        out.push((p.name.clone(), id));
+    }
+    Some(out)
+}
+
+struct Generator<'a> {
+    out: String,
+    variants: &'a BTreeMap<String, String>,
+    params: &'a BTreeMap<String, Vec<Param>>,
+}
+
+impl<'a> Generator<'a> {
+    fn write_tree(&mut self, tree: &MutableKeyTree, depth: usize) -> Result<()> {
+        let map = match tree.as_map() {
+            Some(m) => m.borrow(),
+            None => {
+                return Ok(());
+            },
+        };
+        let indent = "    ".repeat(depth);
+        let root = "super::".repeat(depth);
+
+        let names: Vec<(String, String)> = map.keys().map(|k| (k.clone(), ident(k))).collect();
+        check_unique(&names, "key segment")?;
+
+        for (name, node) in map.iter() {
+            match node.as_key() {
+                Some(key) => {
+                    let variant = &self.variants[key];
+                    self.out.push_str(&format!("{}/// `{}`\n", indent, key));
+                    self.out.push_str(&format!("{}pub const {}: {}Key = {}Key::{};\n",
+                        indent, ident(name).to_uppercase(), root, root, variant));
+
+                    let params = self.params.get(key).and_then(|p| plain_params(p));
+                    if let Some(params) = params {
+                        let generics: Vec<String> = (0..params.len()).map(|i| format!("P{}: Display", i)).collect();
+                        let args: Vec<String> = params.iter().enumerate()
+                            .map(|(i, p)| format!("{}: P{}", p.1, i))
+                            .collect();
+                        let values: Vec<String> = params.iter()
+                            .map(|p| format!("({}, {}.to_string())", quote(&p.0), p.1))
+                            .collect();
+                        self.out.push_str(&format!("{}pub fn {}<{}>(lang: {}Lang, {}) -> String {{\n",
+                            indent, ident(name), generics.join(", "), root, args.join(", ")));
+                        self.out.push_str(&format!("{}    {}format({}get(lang, {}Key::{}), &[{}])\n",
+                            indent, root, root, root, variant, values.join(", ")));
+                        self.out.push_str(&format!("{}}}\n", indent));
+                    }
+                },
+                None => {
+                    self.out.push_str(&format!("{}pub mod {} {{\n", indent, ident(name)));
+                    self.out.push_str(&format!("{}    #[allow(unused_imports)]\n", indent));
+                    self.out.push_str(&format!("{}    use std::fmt::Display;\n\n", indent));
+                    self.write_tree(node, depth + 1)?;
+                    self.out.push_str(&format!("{}}}\n", indent));
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+const FORMAT: &'static str = r#"
+/// Replace `{name}` parameters with their values.
+fn format(pattern: &str, args: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => {
+                out.push_str(&rest[start..]);
+                return out;
+            },
+        };
+        let name = rest[start + 1..end].trim();
+        match args.iter().find(|a| a.0 == name) {
+            Some(a) => out.push_str(&a.1),
+            None => out.push_str(&rest[start..end + 1]),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+"#;
+
+/// Render the module.
+/// `params` holds the parameters of the source value of each key.
+pub fn write_module(project: &str, tree: &MutableKeyTree, keys: &[String],
+                    params: &BTreeMap<String, Vec<Param>>, languages: &[RustLanguage])
+    -> Result<String>
+{
+    let variants: BTreeMap<String, String> = keys.iter()
+        .map(|k| (k.clone(), type_name(k, &['.'])))
+        .collect();
+    let names: Vec<(String, String)> = variants.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    check_unique(&names, "key")?;
+
+    let langs: Vec<(String, String)> = languages.iter()
+        .map(|l| (l.code.clone(), type_name(&l.code, &['-', '_'])))
+        .collect();
+    check_unique(&langs, "language")?;
+
+    let mut out = String::new();
+    out.push_str("// This file was auto-generated. Do not edit by hand!\n");
+    out.push_str(&format!("// Translations of the project {}.\n\n", project.replace('\n', " ")));
+    out.push_str("#![allow(dead_code, non_camel_case_types, unreachable_patterns)]\n\n");
+
+    out.push_str("#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]\npub enum Lang {\n");
+    for &(_, ref variant) in langs.iter() {
+        out.push_str(&format!("    {},\n", variant));
+    }
+    out.push_str("}\n\nimpl Lang {\n");
+    out.push_str("    pub fn code(&self) -> &'static str {\n        match *self {\n");
+    for &(ref code, ref variant) in langs.iter() {
+        out.push_str(&format!("            Lang::{} => {},\n", variant, quote(code)));
+    }
+    out.push_str("        }\n    }\n\n");
+    out.push_str("    pub fn from_code(code: &str) -> Option<Lang> {\n        match code {\n");
+    for &(ref code, ref variant) in langs.iter() {
+        out.push_str(&format!("            {} => Some(Lang::{}),\n", quote(code), variant));
+    }
+    out.push_str("            _ => None,\n        }\n    }\n}\n\n");
+
+    out.push_str("#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]\npub enum Key {\n");
+    for variant in variants.values() {
+        out.push_str(&format!("    {},\n", variant));
+    }
+    out.push_str("}\n\nimpl Key {\n");
+    out.push_str("    pub fn key(&self) -> &'static str {\n        match *self {\n");
+    for (key, variant) in variants.iter() {
+        out.push_str(&format!("            Key::{} => {},\n", variant, quote(key)));
+    }
+    out.push_str("        }\n    }\n\n");
+    out.push_str("    pub fn from_key(key: &str) -> Option<Key> {\n        match key {\n");
+    for (key, variant) in variants.iter() {
+        out.push_str(&format!("            {} => Some(Key::{}),\n", quote(key), variant));
+    }
+    out.push_str("            _ => None,\n        }\n    }\n}\n\n");
+
+    out.push_str("/// Look up a translation.\n/// Keys without a translation return the key itself.\n");
+    out.push_str("pub fn get(lang: Lang, key: Key) -> &'static str {\n    match lang {\n");
+    for (lang, &(_, ref variant)) in languages.iter().zip(langs.iter()) {
+        out.push_str(&format!("        Lang::{} => match key {{\n", variant));
+        for (key, value) in lang.values.iter() {
+            if let Some(variant) = variants.get(key) {
+                out.push_str(&format!("            Key::{} => {},\n", variant, quote(value)));
+            }
+        }
+        out.push_str("            _ => key.key(),\n        },\n");
+    }
+    out.push_str("    }\n}\n\n");
+
+    out.push_str("/// Look up the form of a plural translation for a CLDR category like `one`.\n");
+    out.push_str("/// Missing forms return the other form.\n");
+    out.push_str("pub fn get_plural(lang: Lang, key: Key, category: &str) -> &'static str {\n");
+    out.push_str("    match (lang, key, category) {\n");
+    for (lang, &(_, ref lang_variant)) in languages.iter().zip(langs.iter()) {
+        for (key, forms) in lang.plurals.iter() {
+            let variant = match variants.get(key) {
+                Some(v) => v,
+                None => continue,
+            };
+            for &(category, ref value) in forms {
+                if category != "other" {
+                    out.push_str(&format!("        (Lang::{}, Key::{}, {}) => {},\n",
+                        lang_variant, variant, quote(category), quote(value)));
+                }
+            }
+        }
+    }
+    out.push_str("        _ => get(lang, key),\n    }\n}\n");
+
+    out.push_str(FORMAT);
+
+    out.push_str("\n/// Constants for all keys, following the key hierarchy.\npub mod keys {\n");
+    out.push_str("    #[allow(unused_imports)]\n    use std::fmt::Display;\n\n");
+    let mut generator = Generator {
+        out: String::new(),
+        variants: &variants,
+        params,
+    };
+    generator.write_tree(tree, 1)?;
+    out.push_str(&generator.out);
+    out.push_str("}\n");
+
+    Ok(out)
+}
@@ -0,0 +1,106 @@
+//! Typed TypeScript modules.
+//!
+//! The keys module declares a `TranslationKey` union of all keys and the
+//! parameters each key expects. Every language module declares the shape of
+//! its translations as an interface, where plural keys are objects with a
+//! value per plural category.
+
+use serde_json;
+
+use ::repo::ExportValue;
+use super::params::{Param, ParamKind};
+
+const HEADER: &'static str = "// This file was auto-generated. Do not edit by hand!\n\n/* tslint:disable */\n";
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn param_type(kind: ParamKind) -> &'static str {
+    match kind {
+        ParamKind::Any => "string | number",
+        ParamKind::Number => "number",
+        ParamKind::Date => "Date",
+        ParamKind::Text => "string",
+    }
+}
+
+fn write_union(out: &mut String, name: &str, keys: &[&str]) {
+    if keys.is_empty() {
+        out.push_str(&format!("export type {} = never;\n", name));
+        return;
+    }
+    out.push_str(&format!("export type {} =\n", name));
+    for key in keys {
+        out.push_str(&format!("  | {}\n", quote(key)));
+    }
+    out.pop();
+    out.push_str(";\n");
+}
+
+/// Render the keys module for all keys and the parameters of their source
+/// values.
+pub fn write_keys(keys: &[(String, Vec<Param>)]) -> String {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+
+    let names: Vec<&str> = keys.iter().map(|k| k.0.as_str()).collect();
+    write_union(&mut out, "TranslationKey", &names);
+
+    out.push_str("\n/** The parameters expected by each key. */\n");
+    out.push_str("export interface TranslationParams {\n");
+    for &(ref key, ref params) in keys {
+        let fields: Vec<String> = params.iter()
+            .map(|p| format!("{}: {}", quote(&p.name), param_type(p.kind)))
+            .collect();
+        if fields.is_empty() {
+            out.push_str(&format!("  {}: {{}};\n", quote(key)));
+        } else {
+            out.push_str(&format!("  {}: {{ {} }};\n", quote(key), fields.join("; ")));
+        }
+    }
+    out.push_str("}\n");
+
+    out.push_str("\n/** Keys that can be translated without parameters. */\n");
+    let simple: Vec<&str> = keys.iter()
+        .filter(|k| k.1.is_empty())
+        .map(|k| k.0.as_str())
+        .collect();
+    write_union(&mut out, "SimpleTranslationKey", &simple);
+
+    out.push_str("\nexport const translationKeys: TranslationKey[] = [\n");
+    for key in names {
+        out.push_str(&format!("  {},\n", quote(key)));
+    }
+    out.push_str("];\n\nexport default translationKeys;\n");
+    out
+}
+
+/// Render the module of a language with its translated values.
+pub fn write_translations(language: &str, values: &[ExportValue]) -> String {
+    let mut out = String::from(HEADER);
+
+    out.push_str(&format!("\n/** The translations of {}. */\n", language));
+    out.push_str("export interface Translations {\n");
+    for v in values {
+        if v.plurals.is_empty() {
+            out.push_str(&format!("  {}: string;\n", quote(&v.key.key)));
+        } else {
+            let fields: Vec<String> = v.plurals.iter().map(|f| format!("{}: string", f.0)).collect();
+            out.push_str(&format!("  {}: {{ {} }};\n", quote(&v.key.key), fields.join("; ")));
+        }
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("export const translations: Translations = {\n");
+    for v in values {
+        if v.plurals.is_empty() {
+            out.push_str(&format!("  {}: {},\n", quote(&v.key.key), quote(&v.value)));
+        } else {
+            let fields: Vec<String> = v.plurals.iter().map(|f| format!("{}: {}", f.0, quote(&f.1))).collect();
+            out.push_str(&format!("  {}: {{ {} }},\n", quote(&v.key.key), fields.join(", ")));
+        }
+    }
+    out.push_str("};\n\nexport default translations;\n");
+    out
+}
@@ -0,0 +1,385 @@
+//! XLIFF 1.2 and 2.0 documents for exchange with translation agencies.
+//!
+//! Units are identified by their key.
+
+use xml::reader::{EventReader, XmlEvent};
+use xml::attribute::OwnedAttribute;
+
+use ::error::*;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum XliffVersion {
+    V1_2,
+    V2_0,
+}
+
+impl XliffVersion {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "1.2" => Some(XliffVersion::V1_2),
+            "2.0" => Some(XliffVersion::V2_0),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct XliffUnit {
+    pub id: String,
+    pub source: String,
+    pub target: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct XliffDocument {
+    pub source_language: Option<String>,
+    pub target_language: Option<String>,
+    pub units: Vec<XliffUnit>,
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn write_document(version: XliffVersion, original: &str, doc: &XliffDocument) -> String {
+    let source = doc.source_language.as_ref().map(|s| escape(s)).unwrap_or(String::new());
+    let target = doc.target_language.as_ref().map(|s| escape(s)).unwrap_or(String::new());
+
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    match version {
+        XliffVersion::V1_2 => {
+            out.push_str("<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n");
+            out.push_str(&format!(
+                "  <file original=\"{}\" source-language=\"{}\" target-language=\"{}\" datatype=\"plaintext\">\n",
+                escape(original), source, target));
+            out.push_str("    <body>\n");
+            for unit in doc.units.iter() {
+                let id = escape(&unit.id);
+                out.push_str(&format!("      <trans-unit id=\"{}\" resname=\"{}\">\n", id, id));
+                out.push_str(&format!("        <source>{}</source>\n", escape(&unit.source)));
+                if let Some(ref t) = unit.target {
+                    out.push_str(&format!("        <target>{}</target>\n", escape(t)));
+                }
+                if let Some(ref note) = unit.note {
+                    out.push_str(&format!("        <note>{}</note>\n", escape(note)));
+                }
+                out.push_str("      </trans-unit>\n");
+            }
+            out.push_str("    </body>\n  </file>\n</xliff>\n");
+        },
+        XliffVersion::V2_0 => {
+            out.push_str(&format!(
+                "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\" trgLang=\"{}\">\n",
+                source, target));
+            out.push_str(&format!("  <file id=\"f1\" original=\"{}\">\n", escape(original)));
+            for unit in doc.units.iter() {
+                out.push_str(&format!("    <unit id=\"{}\">\n", escape(&unit.id)));
+                if let Some(ref note) = unit.note {
+                    out.push_str(&format!("      <notes>\n        <note>{}</note>\n      </notes>\n", escape(note)));
+                }
+                out.push_str("      <segment>\n");
+                out.push_str(&format!("        <source>{}</source>\n", escape(&unit.source)));
+                if let Some(ref t) = unit.target {
+                    out.push_str(&format!("        <target>{}</target>\n", escape(t)));
+                }
+                out.push_str("      </segment>\n");
+                out.push_str("    </unit>\n");
+            }
+            out.push_str("  </file>\n</xliff>\n");
+        },
+    }
+
+    out
+}
+
+fn attribute(attributes: &[OwnedAttribute], name: &str) -> Option<String> {
+    attributes.iter()
+        .find(|a| a.name.local_name == name)
+        .map(|a| a.value.clone())
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Field {
+    Source,
+    Target,
+    Note,
+    /// A source, target or note that does not belong to the unit.
+    Ignored,
+}
+
+/// Whether the `state` of a target or segment marks it as not translated yet.
+fn untranslated_state(state: Option<String>) -> bool {
+    match state.as_ref().map(|s| s.as_str()) {
+        // XLIFF 1.2 targets.
+        Some("new") | Some("needs-translation") => true,
+        // XLIFF 2.0 segments.
+        Some("initial") => true,
+        _ => false,
+    }
+}
+
+/// Parse an XLIFF 1.2 or 2.0 document.
+/// Inline markup inside of sources and targets is reduced to its text.
+/// Only sources and targets of the unit itself, or of its segments, are
+/// read, which skips suggestions like those in `alt-trans`. Targets in a
+/// state that marks them as not translated yet are left out.
+pub fn parse_document(content: &str) -> Result<XliffDocument> {
+    let mut doc = XliffDocument {
+        source_language: None,
+        target_language: None,
+        units: Vec::new(),
+    };
+    let mut unit: Option<XliffUnit> = None;
+    // The field that text is currently collected for, and the nesting depth
+    // of inline elements within it.
+    let mut field: Option<(Field, usize)> = None;
+    // The elements open within the current unit, outside of fields.
+    let mut path: Vec<String> = Vec::new();
+    // Whether the current segment is not translated yet.
+    let mut untranslated_segment = false;
+
+    for event in EventReader::from_str(content) {
+        let event = event.map_err(|e| Error::from(format!("Invalid XLIFF: {}", e)))?;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                if let Some((f, depth)) = field {
+                    field = Some((f, depth + 1));
+                    continue;
+                }
+
+                match name.local_name.as_str() {
+                    "xliff" => {
+                        doc.source_language = attribute(&attributes, "srcLang");
+                        doc.target_language = attribute(&attributes, "trgLang");
+                    },
+                    "file" => {
+                        if let Some(lang) = attribute(&attributes, "source-language") {
+                            doc.source_language = Some(lang);
+                        }
+                        if let Some(lang) = attribute(&attributes, "target-language") {
+                            doc.target_language = Some(lang);
+                        }
+                    },
+                    "trans-unit" | "unit" => {
+                        let id = match attribute(&attributes, "id") {
+                            Some(id) => id,
+                            None => {
+                                return Err("Invalid XLIFF: unit without id".into());
+                            },
+                        };
+                        untranslated_segment = false;
+                        unit = Some(XliffUnit {
+                            id,
+                            source: String::new(),
+                            target: None,
+                            note: None,
+                        });
+                    },
+                    "source" | "target" | "note" if unit.is_some() => {
+                        let in_segment = match path.last() {
+                            None => true,
+                            Some(parent) => parent == "segment",
+                        };
+                        let in_alternative = path.iter().any(|p| p == "alt-trans");
+                        match name.local_name.as_str() {
+                            "source" if in_segment => {
+                                field = Some((Field::Source, 0));
+                            },
+                            "target" if in_segment && (untranslated_segment ||
+                                                       untranslated_state(attribute(&attributes, "state"))) => {
+                                field = Some((Field::Ignored, 0));
+                            },
+                            "target" if in_segment => {
+                                field = Some((Field::Target, 0));
+                                if let Some(ref mut u) = unit {
+                                    if u.target.is_none() {
+                                        u.target = Some(String::new());
+                                    }
+                                }
+                            },
+                            "note" if !in_alternative => {
+                                field = Some((Field::Note, 0));
+                            },
+                            _ => {
+                                // Ignore the element and everything in it.
+                                field = Some((Field::Ignored, 0));
+                            },
+                        }
+                    },
+                    other if unit.is_some() => {
+                        if other == "segment" {
+                            untranslated_segment = untranslated_state(attribute(&attributes, "state"));
+                        }
+                        path.push(other.to_string());
+                    },
+                    _ => {},
+                }
+            },
+            XmlEvent::EndElement { name } => {
+                if let Some((f, depth)) = field {
+                    field = if depth > 0 { Some((f, depth - 1)) } else { None };
+                    continue;
+                }
+
+                if path.pop().is_some() {
+                    continue;
+                }
+                match name.local_name.as_str() {
+                    "trans-unit" | "unit" => {
+                        if let Some(u) = unit.take() {
+                            doc.units.push(u);
+                        }
+                    },
+                    _ => {},
+                }
+            },
+            XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                let u = match unit {
+                    Some(ref mut u) => u,
+                    None => {
+                        continue;
+                    },
+                };
+                match field {
+                    Some((Field::Source, _)) => u.source.push_str(&text),
+                    Some((Field::Target, _)) => {
+                        if let Some(ref mut t) = u.target {
+                            t.push_str(&text);
+                        }
+                    },
+                    Some((Field::Note, _)) => {
+                        let note = u.note.get_or_insert(String::new());
+                        note.push_str(&text);
+                    },
+                    Some((Field::Ignored, _)) | None => {},
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(id: &str, source: &str, target: Option<&str>, note: Option<&str>) -> XliffUnit {
+        XliffUnit {
+            id: id.to_string(),
+            source: source.to_string(),
+            target: target.map(|t| t.to_string()),
+            note: note.map(|n| n.to_string()),
+        }
+    }
+
+    fn document() -> XliffDocument {
+        XliffDocument {
+            source_language: Some("en".to_string()),
+            target_language: Some("de".to_string()),
+            units: vec![
+                unit("a.b", "Hello <b>{name}</b> & \"you\"", Some("Hallo <b>{name}</b> & 'du'"), Some("A greeting")),
+                unit("c", "Multi\nline", Some("Mehrere\nZeilen"), None),
+                unit("d", "Untranslated", None, None),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip_v1_2() {
+        let doc = document();
+        let parsed = parse_document(&write_document(XliffVersion::V1_2, "p", &doc)).unwrap();
+        assert_eq!(parsed.source_language, doc.source_language);
+        assert_eq!(parsed.target_language, doc.target_language);
+        assert_eq!(parsed.units, doc.units);
+    }
+
+    #[test]
+    fn round_trip_v2_0() {
+        let doc = document();
+        let parsed = parse_document(&write_document(XliffVersion::V2_0, "p", &doc)).unwrap();
+        assert_eq!(parsed.source_language, doc.source_language);
+        assert_eq!(parsed.target_language, doc.target_language);
+        assert_eq!(parsed.units, doc.units);
+    }
+
+    #[test]
+    fn skip_alt_trans() {
+        let content = r#"<xliff version="1.2"><file source-language="en" target-language="de"><body>
+            <trans-unit id="a">
+              <source>Save</source>
+              <target>Speichern</target>
+              <alt-trans>
+                <source>Save all</source>
+                <target>Alles speichern</target>
+                <note>From memory</note>
+              </alt-trans>
+              <note>Button</note>
+            </trans-unit>
+            </body></file></xliff>"#;
+        let doc = parse_document(content).unwrap();
+        assert_eq!(doc.units, vec![unit("a", "Save", Some("Speichern"), Some("Button"))]);
+    }
+
+    #[test]
+    fn reduce_inline_markup() {
+        let content = r#"<xliff version="1.2"><file><body>
+            <trans-unit id="a"><source>Hi <g id="1">there</g></source><target>Hallo <g id="1">da</g></target></trans-unit>
+            </body></file></xliff>"#;
+        let doc = parse_document(content).unwrap();
+        assert_eq!(doc.units, vec![unit("a", "Hi there", Some("Hallo da"), None)]);
+    }
+
+    #[test]
+    fn missing_targets() {
+        let content = r#"<xliff version="1.2"><file><body>
+            <trans-unit id="a"><source>A</source></trans-unit>
+            <trans-unit id="b"><source>B</source><target/></trans-unit>
+            </body></file></xliff>"#;
+        let doc = parse_document(content).unwrap();
+        assert_eq!(doc.units, vec![unit("a", "A", None, None), unit("b", "B", Some(""), None)]);
+    }
+
+    #[test]
+    fn skip_untranslated_states() {
+        let content = r#"<xliff version="1.2"><file><body>
+            <trans-unit id="a"><source>A</source><target state="new">A</target></trans-unit>
+            <trans-unit id="b"><source>B</source><target state="needs-translation">B</target></trans-unit>
+            <trans-unit id="c"><source>C</source><target state="translated">Ce</target></trans-unit>
+            </body></file></xliff>"#;
+        let doc = parse_document(content).unwrap();
+        assert_eq!(doc.units, vec![
+            unit("a", "A", None, None),
+            unit("b", "B", None, None),
+            unit("c", "C", Some("Ce"), None),
+        ]);
+
+        let content = r#"<xliff version="2.0" srcLang="en" trgLang="de"><file id="f1">
+            <unit id="a"><segment state="initial"><source>A</source><target>A</target></segment></unit>
+            <unit id="b"><segment state="final"><source>B</source><target>Be</target></segment></unit>
+            </file></xliff>"#;
+        let doc = parse_document(content).unwrap();
+        assert_eq!(doc.units, vec![unit("a", "A", None, None), unit("b", "B", Some("Be"), None)]);
+    }
+
+    #[test]
+    fn reject_invalid_documents() {
+        assert!(parse_document("<xliff><file><body><trans-unit>").is_err());
+        assert!(parse_document(r#"<xliff version="1.2"><file><body><trans-unit><source>A</source></trans-unit></body></file></xliff>"#).is_err());
+    }
+}
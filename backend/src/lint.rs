@@ -0,0 +1,326 @@
+//! Consistency checks of translated values against their source values.
+//!
+//! A translation must use the same placeholders as the source value, in the
+//! placeholder syntax of the project, and the same HTML tags.
+
+use std::fmt;
+
+use regex::Regex;
+
+use ::db::schema::PlaceholderSyntax;
+
+const VOID_TAGS: [&'static str; 6] = ["br", "hr", "img", "input", "link", "meta"];
+
+lazy_static! {
+    static ref PRINTF: Regex = Regex::new(
+        r"%(?:(\d+)\$)?[-+ 0#']*(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:hh|h|ll|l|L|q|j|z|t)?([diouxXeEfFgGaAcspn@%])").unwrap();
+    static ref MUSTACHE: Regex = Regex::new(r"\{\{\{?\s*([^{}\s]+)\s*\}?\}\}").unwrap();
+    static ref TAG: Regex = Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9-]*)(?:\s[^<>]*?)?(/?)>").unwrap();
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum IssueKind {
+    MissingPlaceholder,
+    ExtraPlaceholder,
+    /// A placeholder of the source replaced by another one.
+    RenamedPlaceholder,
+    MissingTag,
+    ExtraTag,
+    /// A closing tag that does not match the innermost open tag.
+    MisnestedTag,
+}
+
+impl IssueKind {
+    pub fn to_str(&self) -> &'static str {
+        match *self {
+            IssueKind::MissingPlaceholder => "missing-placeholder",
+            IssueKind::ExtraPlaceholder => "extra-placeholder",
+            IssueKind::RenamedPlaceholder => "renamed-placeholder",
+            IssueKind::MissingTag => "missing-tag",
+            IssueKind::ExtraTag => "extra-tag",
+            IssueKind::MisnestedTag => "misnested-tag",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LintIssue {
+    pub kind: IssueKind,
+    /// The placeholder or tag of the source value.
+    pub expected: Option<String>,
+    /// The placeholder or tag of the translation.
+    pub found: Option<String>,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let expected = self.expected.as_ref().map(|x| x.as_str()).unwrap_or("");
+        let found = self.found.as_ref().map(|x| x.as_str()).unwrap_or("");
+        match self.kind {
+            IssueKind::MissingPlaceholder => write!(f, "Missing placeholder {}", expected),
+            IssueKind::ExtraPlaceholder => write!(f, "Unexpected placeholder {}", found),
+            IssueKind::RenamedPlaceholder => write!(f, "Placeholder {} was renamed to {}", expected, found),
+            IssueKind::MissingTag => write!(f, "Missing tag {}", expected),
+            IssueKind::ExtraTag => write!(f, "Unexpected tag {}", found),
+            IssueKind::MisnestedTag if expected == "" => write!(f, "Tag {} closes no open tag", found),
+            IssueKind::MisnestedTag => write!(f, "Tag {} closes {}", found, expected),
+        }
+    }
+}
+
+fn push_unique(out: &mut Vec<String>, value: String) {
+    if !out.contains(&value) {
+        out.push(value);
+    }
+}
+
+/// The placeholders of a value in order of appearance.
+/// Named placeholders are listed once, while unnumbered printf conversions
+/// are listed for every use.
+pub fn placeholders(syntax: PlaceholderSyntax, value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    match syntax {
+        PlaceholderSyntax::Icu => {
+            if let Ok(message) = ::message_format::parse(value) {
+                for arg in ::message_format::arguments(&message) {
+                    push_unique(&mut out, format!("{{{}}}", arg.name));
+                }
+            }
+        },
+        PlaceholderSyntax::Printf => {
+            for cap in PRINTF.captures_iter(value) {
+                let conversion = match &cap[2] {
+                    "%" => continue,
+                    "i" => "d",
+                    c => c,
+                };
+                match cap.get(1) {
+                    Some(position) => push_unique(&mut out, format!("%{}${}", position.as_str(), conversion)),
+                    None => out.push(format!("%{}", conversion)),
+                }
+            }
+        },
+        PlaceholderSyntax::Mustache => {
+            for cap in MUSTACHE.captures_iter(value) {
+                push_unique(&mut out, format!("{{{{{}}}}}", &cap[1]));
+            }
+        },
+    }
+    out
+}
+
+/// The HTML tags of a value without their attributes, like `<a>`, `</a>`
+/// and `<br>`. Void elements are written without a slash.
+pub fn tags(value: &str) -> Vec<String> {
+    TAG.captures_iter(value)
+        .map(|cap| {
+            let name = cap[2].to_lowercase();
+            let close = if VOID_TAGS.contains(&name.as_str()) { "" } else { &cap[3] };
+            format!("<{}{}{}>", &cap[1], name, close)
+        })
+        .collect()
+}
+
+fn unique(values: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    for value in values {
+        push_unique(&mut out, value.clone());
+    }
+    out
+}
+
+/// The entries of `a` without a counterpart in `b`, counting repetitions.
+fn difference(a: &[String], b: &[String]) -> Vec<String> {
+    let mut rest: Vec<&String> = b.iter().collect();
+    let mut out = Vec::new();
+    for x in a {
+        match rest.iter().position(|y| *y == x) {
+            Some(i) => {
+                rest.remove(i);
+            },
+            None => out.push(x.clone()),
+        }
+    }
+    out
+}
+
+/// The first closing tag that does not match the innermost open tag, with
+/// the tag it was expected to close.
+fn misnested_tag(tags: &[String]) -> Option<(String, String)> {
+    let mut open: Vec<&str> = Vec::new();
+    for tag in tags {
+        if tag.ends_with("/>") {
+            continue;
+        }
+        let name = tag.trim_matches(|c| c == '<' || c == '>' || c == '/');
+        if VOID_TAGS.contains(&name) {
+            continue;
+        }
+        if !tag.starts_with("</") {
+            open.push(name);
+            continue;
+        }
+        match open.pop() {
+            Some(expected) if expected == name => {},
+            Some(expected) => {
+                return Some((format!("<{}>", expected), tag.clone()));
+            },
+            None => {
+                return Some((String::new(), tag.clone()));
+            },
+        }
+    }
+    None
+}
+
+/// Compare a translated value with its source value.
+pub fn lint(syntax: PlaceholderSyntax, source: &str, value: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    let expected = placeholders(syntax, source);
+    let found = placeholders(syntax, value);
+    let missing = difference(&expected, &found);
+    let extra = difference(&found, &expected);
+    // Pair up missing and extra placeholders as renames.
+    for i in 0..missing.len().max(extra.len()) {
+        let (kind, expected, found) = match (missing.get(i), extra.get(i)) {
+            (Some(m), Some(e)) => (IssueKind::RenamedPlaceholder, Some(m.clone()), Some(e.clone())),
+            (Some(m), None) => (IssueKind::MissingPlaceholder, Some(m.clone()), None),
+            (None, Some(e)) => (IssueKind::ExtraPlaceholder, None, Some(e.clone())),
+            (None, None) => unreachable!(),
+        };
+        issues.push(LintIssue { kind, expected, found });
+    }
+
+    let expected = tags(source);
+    let found = tags(value);
+    // Tags repeat in every option of plural and select arguments, whose
+    // number differs between languages, so messages only need the same tags.
+    let (expected_tags, found_tags) = if syntax == PlaceholderSyntax::Icu {
+        (unique(&expected), unique(&found))
+    } else {
+        (expected.clone(), found.clone())
+    };
+    for tag in difference(&expected_tags, &found_tags) {
+        issues.push(LintIssue {
+            kind: IssueKind::MissingTag,
+            expected: Some(tag),
+            found: None,
+        });
+    }
+    for tag in difference(&found_tags, &expected_tags) {
+        issues.push(LintIssue {
+            kind: IssueKind::ExtraTag,
+            expected: None,
+            found: Some(tag),
+        });
+    }
+    if misnested_tag(&expected).is_none() {
+        if let Some((open, close)) = misnested_tag(&found) {
+            issues.push(LintIssue {
+                kind: IssueKind::MisnestedTag,
+                expected: if open.is_empty() { None } else { Some(open) },
+                found: Some(close),
+            });
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::db::schema::PlaceholderSyntax::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn issue(kind: IssueKind, expected: Option<&str>, found: Option<&str>) -> LintIssue {
+        LintIssue {
+            kind,
+            expected: expected.map(|x| x.to_string()),
+            found: found.map(|x| x.to_string()),
+        }
+    }
+
+    #[test]
+    fn list_placeholders() {
+        assert_eq!(placeholders(Icu, "{a} and {b, number} and {a}"), strings(&["{a}", "{b}"]));
+        assert_eq!(placeholders(Printf, "%s of %i, %1$s and %1$s, 100%%"), strings(&["%s", "%d", "%1$s"]));
+        assert_eq!(placeholders(Mustache, "{{ a }} {{{b}}} {{a}}"), strings(&["{{a}}", "{{b}}"]));
+        // Invalid messages have no placeholders.
+        assert!(placeholders(Icu, "{a").is_empty());
+    }
+
+    #[test]
+    fn list_tags() {
+        assert_eq!(tags("<B class=\"x\">a<br/></B><img src=x><x-y/>"),
+                   strings(&["<b>", "<br>", "</b>", "<img>", "<x-y/>"]));
+    }
+
+    #[test]
+    fn pair_renamed_placeholders() {
+        assert_eq!(lint(Icu, "Hi {name}, you have {count}", "Hallo {nom}, du hast {count}"), vec![
+            issue(IssueKind::RenamedPlaceholder, Some("{name}"), Some("{nom}")),
+        ]);
+        // Placeholders are paired in order, and the rest are reported as
+        // missing or extra.
+        assert_eq!(lint(Icu, "{a} {b} {c}", "{x} {c}"), vec![
+            issue(IssueKind::RenamedPlaceholder, Some("{a}"), Some("{x}")),
+            issue(IssueKind::MissingPlaceholder, Some("{b}"), None),
+        ]);
+        assert_eq!(lint(Icu, "{a}", "{a} {b} {c}"), vec![
+            issue(IssueKind::ExtraPlaceholder, None, Some("{b}")),
+            issue(IssueKind::ExtraPlaceholder, None, Some("{c}")),
+        ]);
+        // Order does not matter.
+        assert!(lint(Printf, "%1$s has %2$d", "%2$d hat %1$s").is_empty());
+    }
+
+    #[test]
+    fn count_repeated_printf_conversions() {
+        assert!(lint(Printf, "%s and %s", "%s und %s").is_empty());
+        assert_eq!(lint(Printf, "%s and %s", "%s"), vec![
+            issue(IssueKind::MissingPlaceholder, Some("%s"), None),
+        ]);
+        assert_eq!(lint(Printf, "%s has %d", "%d hat %d"), vec![
+            issue(IssueKind::RenamedPlaceholder, Some("%s"), Some("%d")),
+        ]);
+        // Escaped percent signs are no conversions.
+        assert!(lint(Printf, "100%%", "100 %%").is_empty());
+    }
+
+    #[test]
+    fn deduplicate_icu_tags() {
+        let source = "{n, plural, one {<b>#</b> file} other {<b>#</b> files}}";
+        assert!(lint(Icu, source, "{n, plural, other {<b>#</b> ファイル}}").is_empty());
+        assert!(lint(Icu, source, "{n, plural, one {<b>#</b> plik} few {<b>#</b> pliki} many {<b>#</b> plików} other {<b>#</b> pliku}}").is_empty());
+        assert_eq!(lint(Icu, source, "{n, plural, other {# ファイル}}"), vec![
+            issue(IssueKind::MissingTag, Some("<b>"), None),
+            issue(IssueKind::MissingTag, Some("</b>"), None),
+        ]);
+        // Other syntaxes count every tag.
+        assert_eq!(lint(Printf, "<br>%s<br>", "<br>%s"), vec![
+            issue(IssueKind::MissingTag, Some("<br>"), None),
+        ]);
+    }
+
+    #[test]
+    fn find_misnested_tags() {
+        assert_eq!(misnested_tag(&tags("<b><i>x</i></b><br>")), None);
+        assert_eq!(misnested_tag(&tags("<b><i>x</b></i>")), Some(("<i>".to_string(), "</b>".to_string())));
+        assert_eq!(misnested_tag(&tags("x</b><b>")), Some((String::new(), "</b>".to_string())));
+
+        assert_eq!(lint(Icu, "<b><i>x</i></b>", "<b><i>x</b></i>"), vec![
+            issue(IssueKind::MisnestedTag, Some("<i>"), Some("</b>")),
+        ]);
+        let issues = lint(Icu, "<b>x</b>", "</b>x<b>");
+        assert_eq!(issues, vec![issue(IssueKind::MisnestedTag, None, Some("</b>"))]);
+        assert_eq!(issues[0].to_string(), "Tag </b> closes no open tag");
+        // Sources that are misnested themselves are not held against the
+        // translation.
+        assert!(lint(Icu, "</b>x<b>", "</b>y<b>").is_empty());
+    }
+}
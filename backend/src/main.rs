@@ -20,11 +20,19 @@ extern crate simple_jwt;
 extern crate juniper_rocket;
 extern crate uuid;
 extern crate regex;
+#[macro_use] extern crate lazy_static;
+extern crate xml;
+extern crate zip;
 
 mod error;
 mod commands;
 mod db;
 mod config;
+mod diff;
+mod formats;
+mod lint;
+mod message_format;
+mod plurals;
 mod repo;
 mod app;
 mod api;
@@ -0,0 +1,602 @@
+//! ICU MessageFormat parsing.
+//!
+//! Follows the ICU syntax: `{name}` arguments, formatted arguments like
+//! `{n, number, percent}`, `plural`, `selectordinal` and `select` arguments
+//! with `#` inside of plural options, and apostrophe quoting where `''` is a
+//! literal apostrophe and `'{'` a literal brace.
+
+use std::fmt;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Element {
+    Text(String),
+    /// `#` inside of a plural option, replaced by the number.
+    Pound,
+    Argument(Argument),
+}
+
+pub type Message = Vec<Element>;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Argument {
+    pub name: String,
+    pub format: ArgumentFormat,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum ArgumentFormat {
+    /// `{name}`
+    None,
+    /// `{name, number}` or `{name, date, short}`.
+    Simple {
+        kind: String,
+        style: Option<String>,
+    },
+    /// `{name, plural, one {...} other {...}}`, or `selectordinal`.
+    Plural {
+        ordinal: bool,
+        offset: u32,
+        options: Vec<(String, Message)>,
+    },
+    /// `{name, select, a {...} other {...}}`
+    Select {
+        options: Vec<(String, Message)>,
+    },
+}
+
+impl ArgumentFormat {
+    /// The argument type as written in the message.
+    pub fn kind(&self) -> Option<&str> {
+        match *self {
+            ArgumentFormat::None => None,
+            ArgumentFormat::Simple { ref kind, .. } => Some(kind),
+            ArgumentFormat::Plural { ordinal: false, .. } => Some("plural"),
+            ArgumentFormat::Plural { ordinal: true, .. } => Some("selectordinal"),
+            ArgumentFormat::Select { .. } => Some("select"),
+        }
+    }
+}
+
+const SIMPLE_TYPES: [&'static str; 6] = ["number", "date", "time", "spellout", "ordinal", "duration"];
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    /// Character offset of the error, starting at 0.
+    pub position: usize,
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error, starting at 1.
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+type ParseResult<T> = ::std::result::Result<T, ParseError>;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<S: Into<String>>(&self, position: usize, message: S) -> ParseError {
+        let mut line = 1;
+        let mut column = 1;
+        for c in self.chars.iter().take(position) {
+            if *c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        ParseError {
+            message: message.into(),
+            position,
+            line,
+            column,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn expect(&mut self, expected: char, message: &str) -> ParseResult<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(self.pos, message))
+        }
+    }
+
+    /// Parse text and arguments until the closing brace of a nested message,
+    /// or the end of the input. `in_plural` is set inside the options of a
+    /// plural argument, including selects nested in them, where `#` stands for
+    /// the number.
+    fn message(&mut self, nested: bool, in_plural: bool) -> ParseResult<Message> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => break,
+            };
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        elements.push(Element::Text(text.clone()));
+                        text.clear();
+                    }
+                    elements.push(Element::Argument(self.argument(in_plural)?));
+                },
+                '}' if nested => break,
+                '}' => {
+                    return Err(self.error(self.pos, "Unmatched }"));
+                },
+                '#' if in_plural => {
+                    if !text.is_empty() {
+                        elements.push(Element::Text(text.clone()));
+                        text.clear();
+                    }
+                    elements.push(Element::Pound);
+                    self.pos += 1;
+                },
+                '\'' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\'') => {
+                            text.push('\'');
+                            self.pos += 1;
+                        },
+                        Some(next) if next == '{' || next == '}' || next == '|' || (in_plural && next == '#') => {
+                            // Quoted literal text up to the next single apostrophe.
+                            loop {
+                                match self.peek() {
+                                    None => break,
+                                    Some('\'') if self.chars.get(self.pos + 1) == Some(&'\'') => {
+                                        text.push('\'');
+                                        self.pos += 2;
+                                    },
+                                    Some('\'') => {
+                                        self.pos += 1;
+                                        break;
+                                    },
+                                    Some(c) => {
+                                        text.push(c);
+                                        self.pos += 1;
+                                    },
+                                }
+                            }
+                        },
+                        _ => text.push('\''),
+                    }
+                },
+                c => {
+                    text.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+
+        if !text.is_empty() {
+            elements.push(Element::Text(text));
+        }
+        Ok(elements)
+    }
+
+    fn argument(&mut self, in_plural: bool) -> ParseResult<Argument> {
+        let start = self.pos;
+        self.pos += 1;
+        self.skip_whitespace();
+
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(self.error(self.pos, "Expected an argument name"));
+        }
+        self.skip_whitespace();
+
+        let format = match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                ArgumentFormat::None
+            },
+            Some(',') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let kind_pos = self.pos;
+                let kind = self.identifier();
+                match kind.as_str() {
+                    "" => {
+                        return Err(self.error(kind_pos, "Expected an argument type"));
+                    },
+                    "plural" | "selectordinal" => {
+                        self.expect(',', "Expected , after the argument type")?;
+                        let (offset, options) = self.options(start, true, in_plural)?;
+                        ArgumentFormat::Plural {
+                            ordinal: kind == "selectordinal",
+                            offset,
+                            options,
+                        }
+                    },
+                    "select" => {
+                        self.expect(',', "Expected , after the argument type")?;
+                        let (_, options) = self.options(start, false, in_plural)?;
+                        ArgumentFormat::Select { options }
+                    },
+                    k if SIMPLE_TYPES.contains(&k) => {
+                        let style = self.style(start)?;
+                        ArgumentFormat::Simple { kind: kind.clone(), style }
+                    },
+                    _ => {
+                        return Err(self.error(kind_pos, format!("Unknown argument type {}", kind)));
+                    },
+                }
+            },
+            Some(_) => {
+                return Err(self.error(self.pos, "Expected , or } after the argument name"));
+            },
+            None => {
+                return Err(self.error(start, "Unclosed argument"));
+            },
+        };
+
+        Ok(Argument { name, format })
+    }
+
+    /// Parse the optional style of a simple argument, including the closing
+    /// brace.
+    fn style(&mut self, start: usize) -> ParseResult<Option<String>> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('}') => {
+                self.pos += 1;
+                return Ok(None);
+            },
+            Some(',') => {
+                self.pos += 1;
+            },
+            Some(_) => {
+                return Err(self.error(self.pos, "Expected , or } after the argument type"));
+            },
+            None => {
+                return Err(self.error(start, "Unclosed argument"));
+            },
+        }
+
+        let style_pos = self.pos;
+        let mut style = String::new();
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(self.error(start, "Unclosed argument"));
+                },
+                Some('}') if depth == 0 => {
+                    self.pos += 1;
+                    break;
+                },
+                Some(c) => {
+                    if c == '{' {
+                        depth += 1;
+                    } else if c == '}' {
+                        depth -= 1;
+                    }
+                    style.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+
+        let style = style.trim().to_string();
+        if style.is_empty() {
+            return Err(self.error(style_pos, "Expected an argument style"));
+        }
+        Ok(Some(style))
+    }
+
+    /// Parse the options of a plural or select argument, including the
+    /// closing brace.
+    fn options(&mut self, start: usize, plural: bool, in_plural: bool) -> ParseResult<(u32, Vec<(String, Message)>)> {
+        let mut offset = 0;
+        let mut options: Vec<(String, Message)> = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            let selector_pos = self.pos;
+            match self.peek() {
+                None => {
+                    return Err(self.error(start, "Unclosed argument"));
+                },
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                },
+                _ => {},
+            }
+
+            let selector = if plural && self.peek() == Some('=') {
+                self.pos += 1;
+                let digits = self.identifier();
+                if digits.is_empty() || !digits.chars().all(|c| c.is_digit(10)) {
+                    return Err(self.error(selector_pos, "Expected a number after ="));
+                }
+                format!("={}", digits)
+            } else {
+                let selector = self.identifier();
+                if selector.is_empty() {
+                    return Err(self.error(selector_pos, "Expected a selector"));
+                }
+                if plural && selector == "offset" && self.peek() == Some(':') {
+                    if !options.is_empty() {
+                        return Err(self.error(selector_pos, "The offset must come before all options"));
+                    }
+                    self.pos += 1;
+                    let digits_pos = self.pos;
+                    let digits = self.identifier();
+                    offset = match digits.parse() {
+                        Ok(o) => o,
+                        Err(_) => {
+                            return Err(self.error(digits_pos, "Expected a number after offset:"));
+                        },
+                    };
+                    continue;
+                }
+                if plural && !::plurals::CATEGORIES.contains(&selector.as_str()) {
+                    return Err(self.error(selector_pos, format!("Invalid plural category {}", selector)));
+                }
+                selector
+            };
+
+            if options.iter().any(|o| o.0 == selector) {
+                return Err(self.error(selector_pos, format!("Duplicate option {}", selector)));
+            }
+
+            self.expect('{', "Expected { after the selector")?;
+            let message = self.message(true, plural || in_plural)?;
+            if self.peek() != Some('}') {
+                return Err(self.error(selector_pos, format!("Unclosed option {}", selector)));
+            }
+            self.pos += 1;
+            options.push((selector, message));
+        }
+
+        if !options.iter().any(|o| o.0 == "other") {
+            return Err(self.error(start, "Missing the other option"));
+        }
+        Ok((offset, options))
+    }
+}
+
+/// The result of validating a message.
+#[derive(Debug, Clone)]
+pub struct Validation {
+    pub error: Option<ParseError>,
+    /// Names of the arguments, without duplicates.
+    pub arguments: Vec<String>,
+}
+
+pub fn validate(value: &str) -> Validation {
+    match parse(value) {
+        Ok(message) => {
+            let mut names: Vec<String> = Vec::new();
+            for arg in arguments(&message) {
+                if !names.contains(&arg.name) {
+                    names.push(arg.name.clone());
+                }
+            }
+            Validation {
+                error: None,
+                arguments: names,
+            }
+        },
+        Err(e) => Validation {
+            error: Some(e),
+            arguments: Vec::new(),
+        },
+    }
+}
+
+pub fn parse(value: &str) -> ParseResult<Message> {
+    let mut parser = Parser {
+        chars: value.chars().collect(),
+        pos: 0,
+    };
+    parser.message(false, false)
+}
+
+/// All arguments of a message and the arguments nested within their options,
+/// in order of appearance.
+pub fn arguments(message: &Message) -> Vec<&Argument> {
+    let mut out = Vec::new();
+    for element in message {
+        if let Element::Argument(ref arg) = *element {
+            out.push(arg);
+            match arg.format {
+                ArgumentFormat::Plural { ref options, .. } | ArgumentFormat::Select { ref options } => {
+                    for &(_, ref nested) in options {
+                        out.extend(arguments(nested));
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Element {
+        Element::Text(s.to_string())
+    }
+
+    fn names(value: &str) -> Vec<String> {
+        let message = parse(value).unwrap();
+        arguments(&message).iter().map(|a| a.name.clone()).collect()
+    }
+
+    #[test]
+    fn parse_simple_arguments() {
+        let message = parse("Hello {name}, it is {now, time, short}").unwrap();
+        assert_eq!(message, vec![
+            text("Hello "),
+            Element::Argument(Argument {
+                name: "name".to_string(),
+                format: ArgumentFormat::None,
+            }),
+            text(", it is "),
+            Element::Argument(Argument {
+                name: "now".to_string(),
+                format: ArgumentFormat::Simple {
+                    kind: "time".to_string(),
+                    style: Some("short".to_string()),
+                },
+            }),
+        ]);
+    }
+
+    #[test]
+    fn parse_nested_plural_and_select() {
+        let value = "{gender, select, female {{n, plural, offset:1 =0 {none} one {# by {who}} other {# items}}} other {{n, number}}}";
+        assert_eq!(names(value), vec!["gender", "n", "who", "n"]);
+
+        let message = parse(value).unwrap();
+        let options = match message[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Select { ref options }, .. }) => options,
+            _ => panic!("expected a select argument"),
+        };
+        assert_eq!(options[0].0, "female");
+        match options[0].1[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Plural { ordinal, offset, ref options }, .. }) => {
+                assert!(!ordinal);
+                assert_eq!(offset, 1);
+                let selectors: Vec<&str> = options.iter().map(|o| o.0.as_str()).collect();
+                assert_eq!(selectors, vec!["=0", "one", "other"]);
+            },
+            _ => panic!("expected a plural argument"),
+        }
+    }
+
+    #[test]
+    fn parse_pound_only_in_plurals() {
+        let message = parse("#{n, plural, other {# items}}").unwrap();
+        assert_eq!(message[0], text("#"));
+        match message[1] {
+            Element::Argument(Argument { format: ArgumentFormat::Plural { ref options, .. }, .. }) => {
+                assert_eq!(options[0].1, vec![Element::Pound, text(" items")]);
+            },
+            _ => panic!("expected a plural argument"),
+        }
+
+        match parse("{s, select, other {#}}").unwrap()[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Select { ref options }, .. }) => {
+                assert_eq!(options[0].1, vec![text("#")]);
+            },
+            _ => panic!("expected a select argument"),
+        }
+    }
+
+    #[test]
+    fn parse_pound_in_select_inside_plural() {
+        let message = parse("{n, plural, other {{g, select, female {# by her} other {#}}}}").unwrap();
+        let options = match message[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Plural { ref options, .. }, .. }) => options,
+            _ => panic!("expected a plural argument"),
+        };
+        match options[0].1[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Select { ref options }, .. }) => {
+                assert_eq!(options[0].1, vec![Element::Pound, text(" by her")]);
+                assert_eq!(options[1].1, vec![Element::Pound]);
+            },
+            _ => panic!("expected a select argument"),
+        }
+    }
+
+    #[test]
+    fn parse_apostrophes() {
+        assert_eq!(parse("It''s").unwrap(), vec![text("It's")]);
+        assert_eq!(parse("It's").unwrap(), vec![text("It's")]);
+        assert_eq!(parse("'{name}' and '}'").unwrap(), vec![text("{name} and }")]);
+        assert_eq!(parse("'{it''s}'").unwrap(), vec![text("{it's}")]);
+
+        match parse("{n, plural, other {'#' #}}").unwrap()[0] {
+            Element::Argument(Argument { format: ArgumentFormat::Plural { ref options, .. }, .. }) => {
+                assert_eq!(options[0].1, vec![text("# "), Element::Pound]);
+            },
+            _ => panic!("expected a plural argument"),
+        }
+    }
+
+    fn error(value: &str) -> ParseError {
+        parse(value).unwrap_err()
+    }
+
+    #[test]
+    fn error_positions() {
+        let e = error("Hello {name");
+        assert_eq!((e.position, e.line, e.column), (6, 1, 7));
+        assert_eq!(e.message, "Unclosed argument");
+
+        let e = error("First line\n{, x}");
+        assert_eq!((e.position, e.line, e.column), (12, 2, 2));
+        assert_eq!(e.message, "Expected an argument name");
+
+        let e = error("{n, foo}");
+        assert_eq!((e.position, e.column), (4, 5));
+        assert_eq!(e.message, "Unknown argument type foo");
+
+        let e = error("{n, plural, eins {x} other {y}}");
+        assert_eq!(e.position, 12);
+        assert_eq!(e.message, "Invalid plural category eins");
+
+        let e = error("Hello {name}}");
+        assert_eq!((e.position, e.column), (12, 13));
+        assert_eq!(e.message, "Unmatched }");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("{n, plural, one {x}}").message, "Missing the other option");
+        assert_eq!(error("{n, select, a {x} a {y} other {z}}").message, "Duplicate option a");
+        assert_eq!(error("{n, number, }").message, "Expected an argument style");
+        assert_eq!(error("{n number}").message, "Expected , or } after the argument name");
+        assert_eq!(error("{n, plural, one {x} offset:1 other {y}}").message, "The offset must come before all options");
+        assert_eq!(error("{n, plural, =x {x} other {y}}").message, "Expected a number after =");
+    }
+
+    #[test]
+    fn validate_lists_arguments_once() {
+        let validation = validate("{a} {b} {a}");
+        assert!(validation.error.is_none());
+        assert_eq!(validation.arguments, vec!["a", "b"]);
+
+        let validation = validate("{a");
+        assert!(validation.error.is_some());
+        assert!(validation.arguments.is_empty());
+    }
+}
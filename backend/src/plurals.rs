@@ -0,0 +1,374 @@
+//! CLDR plural rules.
+//!
+//! Languages pick one of the CLDR plural categories for every number. Which
+//! categories a language uses follows from its code. Every rule also carries
+//! the matching gettext `Plural-Forms` expression. Gettext only counts
+//! integers, so categories that only cover fractions have no gettext form.
+
+/// All CLDR categories, in their canonical order.
+pub const CATEGORIES: [&'static str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+#[derive(Debug)]
+pub struct PluralRule {
+    /// The categories of the language, in canonical order.
+    pub categories: &'static [&'static str],
+    /// The categories integers fall into, in the order of the gettext forms.
+    pub gettext_categories: &'static [&'static str],
+    /// The gettext `Plural-Forms` header.
+    pub gettext: &'static str,
+}
+
+static OTHER: PluralRule = PluralRule {
+    categories: &["other"],
+    gettext_categories: &["other"],
+    gettext: "nplurals=1; plural=0;",
+};
+
+static ONE_OTHER: PluralRule = PluralRule {
+    categories: &["one", "other"],
+    gettext_categories: &["one", "other"],
+    gettext: "nplurals=2; plural=(n != 1);",
+};
+
+/// One covers 0 and 1.
+static ONE_OTHER_ZERO: PluralRule = PluralRule {
+    categories: &["one", "other"],
+    gettext_categories: &["one", "other"],
+    gettext: "nplurals=2; plural=(n > 1);",
+};
+
+static ONE_OTHER_ICELANDIC: PluralRule = PluralRule {
+    categories: &["one", "other"],
+    gettext_categories: &["one", "other"],
+    gettext: "nplurals=2; plural=(n%10!=1 || n%100==11);",
+};
+
+static ONE_OTHER_FILIPINO: PluralRule = PluralRule {
+    categories: &["one", "other"],
+    gettext_categories: &["one", "other"],
+    gettext: "nplurals=2; plural=(n%10==4 || n%10==6 || n%10==9);",
+};
+
+static ZERO_ONE_OTHER: PluralRule = PluralRule {
+    categories: &["zero", "one", "other"],
+    gettext_categories: &["zero", "one", "other"],
+    gettext: "nplurals=3; plural=(n==0 ? 0 : n==1 ? 1 : 2);",
+};
+
+static ZERO_ONE_OTHER_LATVIAN: PluralRule = PluralRule {
+    categories: &["zero", "one", "other"],
+    gettext_categories: &["zero", "one", "other"],
+    gettext: "nplurals=3; plural=(n%10==0 || (n%100>=11 && n%100<=19) ? 0 : n%10==1 && n%100!=11 ? 1 : 2);",
+};
+
+static ONE_TWO_OTHER: PluralRule = PluralRule {
+    categories: &["one", "two", "other"],
+    gettext_categories: &["one", "two", "other"],
+    gettext: "nplurals=3; plural=(n==1 ? 0 : n==2 ? 1 : 2);",
+};
+
+static ONE_FEW_OTHER_ROMANIAN: PluralRule = PluralRule {
+    categories: &["one", "few", "other"],
+    gettext_categories: &["one", "few", "other"],
+    gettext: "nplurals=3; plural=(n==1 ? 0 : (n==0 || (n%100>0 && n%100<20)) ? 1 : 2);",
+};
+
+static ONE_FEW_OTHER_SERBIAN: PluralRule = PluralRule {
+    categories: &["one", "few", "other"],
+    gettext_categories: &["one", "few", "other"],
+    gettext: "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);",
+};
+
+static ONE_FEW_OTHER_TACHELHIT: PluralRule = PluralRule {
+    categories: &["one", "few", "other"],
+    gettext_categories: &["one", "few", "other"],
+    gettext: "nplurals=3; plural=(n<=1 ? 0 : n<=10 ? 1 : 2);",
+};
+
+static ONE_TWO_FEW_OTHER: PluralRule = PluralRule {
+    categories: &["one", "two", "few", "other"],
+    gettext_categories: &["one", "two", "few", "other"],
+    gettext: "nplurals=4; plural=(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3);",
+};
+
+static ONE_TWO_FEW_OTHER_GAELIC: PluralRule = PluralRule {
+    categories: &["one", "two", "few", "other"],
+    gettext_categories: &["one", "two", "few", "other"],
+    gettext: "nplurals=4; plural=(n==1 || n==11 ? 0 : n==2 || n==12 ? 1 : (n>=3 && n<=10) || (n>=13 && n<=19) ? 2 : 3);",
+};
+
+static ONE_TWO_MANY_OTHER: PluralRule = PluralRule {
+    categories: &["one", "two", "many", "other"],
+    gettext_categories: &["one", "two", "many", "other"],
+    gettext: "nplurals=4; plural=(n==1 ? 0 : n==2 ? 1 : n>10 && n%10==0 ? 2 : 3);",
+};
+
+/// Other only covers fractions.
+static ONE_FEW_MANY_OTHER_RUSSIAN: PluralRule = PluralRule {
+    categories: &["one", "few", "many", "other"],
+    gettext_categories: &["one", "few", "many"],
+    gettext: "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<12 || n%100>14) ? 1 : 2);",
+};
+
+/// Other only covers fractions.
+static ONE_FEW_MANY_OTHER_POLISH: PluralRule = PluralRule {
+    categories: &["one", "few", "many", "other"],
+    gettext_categories: &["one", "few", "many"],
+    gettext: "nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<12 || n%100>14) ? 1 : 2);",
+};
+
+/// Many only covers fractions.
+static ONE_FEW_MANY_OTHER_CZECH: PluralRule = PluralRule {
+    categories: &["one", "few", "many", "other"],
+    gettext_categories: &["one", "few", "other"],
+    gettext: "nplurals=3; plural=(n==1 ? 0 : n>=2 && n<=4 ? 1 : 2);",
+};
+
+/// Many only covers fractions.
+static ONE_FEW_MANY_OTHER_LITHUANIAN: PluralRule = PluralRule {
+    categories: &["one", "few", "many", "other"],
+    gettext_categories: &["one", "few", "other"],
+    gettext: "nplurals=3; plural=(n%10==1 && (n%100<11 || n%100>19) ? 0 : n%10>=2 && (n%100<11 || n%100>19) ? 1 : 2);",
+};
+
+static ONE_FEW_MANY_OTHER_MALTESE: PluralRule = PluralRule {
+    categories: &["one", "few", "many", "other"],
+    gettext_categories: &["one", "few", "many", "other"],
+    gettext: "nplurals=4; plural=(n==1 ? 0 : n==0 || (n%100>=2 && n%100<=10) ? 1 : n%100>=11 && n%100<=19 ? 2 : 3);",
+};
+
+static ONE_TWO_FEW_MANY_OTHER: PluralRule = PluralRule {
+    categories: &["one", "two", "few", "many", "other"],
+    gettext_categories: &["one", "two", "few", "many", "other"],
+    gettext: "nplurals=5; plural=(n==1 ? 0 : n==2 ? 1 : n>=3 && n<=6 ? 2 : n>=7 && n<=10 ? 3 : 4);",
+};
+
+static ALL_ARABIC: PluralRule = PluralRule {
+    categories: &["zero", "one", "two", "few", "many", "other"],
+    gettext_categories: &["zero", "one", "two", "few", "many", "other"],
+    gettext: "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5);",
+};
+
+static ALL_WELSH: PluralRule = PluralRule {
+    categories: &["zero", "one", "two", "few", "many", "other"],
+    gettext_categories: &["zero", "one", "two", "few", "many", "other"],
+    gettext: "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n==3 ? 3 : n==6 ? 4 : 5);",
+};
+
+/// The cardinal plural rule of a language code like `de` or `pt-PT`.
+/// Unknown languages use `one` and `other`, like English.
+pub fn rule(code: &str) -> &'static PluralRule {
+    let code = code.to_lowercase().replace('_', "-");
+    if code == "pt-pt" {
+        return &ONE_OTHER;
+    }
+
+    let lang = code.split('-').next().unwrap_or("");
+    match lang {
+        "bm" | "bo" | "dz" | "id" | "ig" | "ii" | "in" | "ja" | "jbo" | "jv" | "jw" | "kde" |
+        "kea" | "km" | "ko" | "lkt" | "lo" | "ms" | "my" | "nqo" | "sah" | "ses" | "sg" | "th" |
+        "to" | "vi" | "wo" | "yo" | "yue" | "zh" =>
+            &OTHER,
+        "ak" | "am" | "as" | "bh" | "bn" | "fa" | "ff" | "fr" | "gu" | "guw" | "hi" | "hy" |
+        "kab" | "kn" | "ln" | "mg" | "nso" | "pa" | "pt" | "si" | "ti" | "wa" | "zu" =>
+            &ONE_OTHER_ZERO,
+        "is" | "mk" =>
+            &ONE_OTHER_ICELANDIC,
+        "fil" | "tl" =>
+            &ONE_OTHER_FILIPINO,
+        "ksh" | "lag" =>
+            &ZERO_ONE_OTHER,
+        "lv" | "prg" =>
+            &ZERO_ONE_OTHER_LATVIAN,
+        "iu" | "kw" | "naq" | "se" | "sma" | "smi" | "smj" | "smn" | "sms" =>
+            &ONE_TWO_OTHER,
+        "mo" | "ro" =>
+            &ONE_FEW_OTHER_ROMANIAN,
+        "bs" | "hr" | "sh" | "sr" =>
+            &ONE_FEW_OTHER_SERBIAN,
+        "shi" =>
+            &ONE_FEW_OTHER_TACHELHIT,
+        "dsb" | "hsb" | "sl" =>
+            &ONE_TWO_FEW_OTHER,
+        "gd" =>
+            &ONE_TWO_FEW_OTHER_GAELIC,
+        "he" | "iw" =>
+            &ONE_TWO_MANY_OTHER,
+        "be" | "ru" | "uk" =>
+            &ONE_FEW_MANY_OTHER_RUSSIAN,
+        "pl" =>
+            &ONE_FEW_MANY_OTHER_POLISH,
+        "cs" | "sk" =>
+            &ONE_FEW_MANY_OTHER_CZECH,
+        "lt" =>
+            &ONE_FEW_MANY_OTHER_LITHUANIAN,
+        "mt" =>
+            &ONE_FEW_MANY_OTHER_MALTESE,
+        "ga" =>
+            &ONE_TWO_FEW_MANY_OTHER,
+        "ar" | "ars" =>
+            &ALL_ARABIC,
+        "cy" =>
+            &ALL_WELSH,
+        _ =>
+            &ONE_OTHER,
+    }
+}
+
+/// The static name of a category, or None if it is not a CLDR category.
+pub fn category(name: &str) -> Option<&'static str> {
+    CATEGORIES.iter().find(|c| **c == name).map(|c| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RULES: [&'static PluralRule; 22] = [
+        &OTHER, &ONE_OTHER, &ONE_OTHER_ZERO, &ONE_OTHER_ICELANDIC, &ONE_OTHER_FILIPINO,
+        &ZERO_ONE_OTHER, &ZERO_ONE_OTHER_LATVIAN, &ONE_TWO_OTHER, &ONE_FEW_OTHER_ROMANIAN,
+        &ONE_FEW_OTHER_SERBIAN, &ONE_FEW_OTHER_TACHELHIT, &ONE_TWO_FEW_OTHER, &ONE_TWO_FEW_OTHER_GAELIC,
+        &ONE_TWO_MANY_OTHER, &ONE_FEW_MANY_OTHER_RUSSIAN, &ONE_FEW_MANY_OTHER_POLISH,
+        &ONE_FEW_MANY_OTHER_CZECH, &ONE_FEW_MANY_OTHER_LITHUANIAN, &ONE_FEW_MANY_OTHER_MALTESE,
+        &ONE_TWO_FEW_MANY_OTHER, &ALL_ARABIC, &ALL_WELSH,
+    ];
+
+    /// Evaluates the C expressions of `Plural-Forms` headers.
+    struct Expression<'a> {
+        tokens: Vec<&'a str>,
+        pos: usize,
+    }
+
+    impl<'a> Expression<'a> {
+        fn new(source: &'a str) -> Self {
+            let mut tokens = Vec::new();
+            let mut rest = source.trim();
+            while !rest.is_empty() {
+                let len = if rest.starts_with(|c: char| c.is_digit(10)) {
+                    rest.find(|c: char| !c.is_digit(10)).unwrap_or(rest.len())
+                } else if ["==", "!=", "<=", ">=", "&&", "||"].iter().any(|op| rest.starts_with(op)) {
+                    2
+                } else {
+                    1
+                };
+                tokens.push(&rest[..len]);
+                rest = rest[len..].trim_left();
+            }
+            Expression { tokens, pos: 0 }
+        }
+
+        fn next(&mut self) -> &'a str {
+            let token = self.tokens[self.pos];
+            self.pos += 1;
+            token
+        }
+
+        fn peek(&self) -> Option<&'a str> {
+            self.tokens.get(self.pos).map(|t| *t)
+        }
+
+        fn eval(&mut self, n: u64) -> u64 {
+            self.pos = 0;
+            let value = self.ternary(n);
+            assert_eq!(self.pos, self.tokens.len(), "Trailing tokens");
+            value
+        }
+
+        fn ternary(&mut self, n: u64) -> u64 {
+            let condition = self.binary(n, 0);
+            if self.peek() != Some("?") {
+                return condition;
+            }
+            self.next();
+            let then = self.ternary(n);
+            assert_eq!(self.next(), ":");
+            let otherwise = self.ternary(n);
+            if condition != 0 { then } else { otherwise }
+        }
+
+        fn binary(&mut self, n: u64, level: usize) -> u64 {
+            const LEVELS: [&'static [&'static str]; 5] = [
+                &["||"], &["&&"], &["==", "!="], &["<", ">", "<=", ">="], &["%"],
+            ];
+            if level == LEVELS.len() {
+                return self.primary(n);
+            }
+            let mut left = self.binary(n, level + 1);
+            while let Some(op) = self.peek() {
+                if !LEVELS[level].contains(&op) {
+                    break;
+                }
+                self.next();
+                let right = self.binary(n, level + 1);
+                left = match op {
+                    "||" => (left != 0 || right != 0) as u64,
+                    "&&" => (left != 0 && right != 0) as u64,
+                    "==" => (left == right) as u64,
+                    "!=" => (left != right) as u64,
+                    "<" => (left < right) as u64,
+                    ">" => (left > right) as u64,
+                    "<=" => (left <= right) as u64,
+                    ">=" => (left >= right) as u64,
+                    _ => left % right,
+                };
+            }
+            left
+        }
+
+        fn primary(&mut self, n: u64) -> u64 {
+            match self.next() {
+                "n" => n,
+                "(" => {
+                    let value = self.ternary(n);
+                    assert_eq!(self.next(), ")");
+                    value
+                },
+                number => number.parse().expect("Invalid token"),
+            }
+        }
+    }
+
+    #[test]
+    fn gettext_expressions_match_categories() {
+        for rule in RULES.iter() {
+            assert!(rule.gettext.starts_with("nplurals=") && rule.gettext.ends_with(';'), "{}", rule.gettext);
+            let mut parts = rule.gettext[..rule.gettext.len() - 1].split("; plural=");
+            let nplurals: u64 = parts.next().unwrap()["nplurals=".len()..].parse().unwrap();
+            let mut expression = Expression::new(parts.next().unwrap());
+            assert_eq!(nplurals, rule.gettext_categories.len() as u64, "{}", rule.gettext);
+
+            let mut seen = vec![false; nplurals as usize];
+            for n in 0..200 {
+                let index = expression.eval(n);
+                assert!(index < nplurals, "{} gives {} for {}", rule.gettext, index, n);
+                seen[index as usize] = true;
+            }
+            assert!(seen.iter().all(|s| *s), "{} misses forms", rule.gettext);
+        }
+    }
+
+    #[test]
+    fn categories_are_canonical() {
+        let position = |c: &&str| CATEGORIES.iter().position(|x| x == c).unwrap();
+        for rule in RULES.iter() {
+            assert!(rule.categories.contains(&"other"));
+            assert!(rule.categories.windows(2).all(|w| position(&w[0]) < position(&w[1])));
+            // Gettext forms are the categories without those that only cover
+            // fractions.
+            let mut rest = rule.categories.iter();
+            assert!(rule.gettext_categories.iter().all(|c| rest.any(|x| x == c)), "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn rule_by_code() {
+        assert_eq!(rule("de").categories, &["one", "other"]);
+        assert_eq!(rule("pt-BR").gettext, "nplurals=2; plural=(n > 1);");
+        assert_eq!(rule("pt_PT").gettext, "nplurals=2; plural=(n != 1);");
+        assert_eq!(rule("RU").gettext_categories, &["one", "few", "many"]);
+        assert_eq!(rule("zh-Hant").categories, &["other"]);
+        assert_eq!(rule("xx").categories, &["one", "other"]);
+        assert_eq!(category("few"), Some("few"));
+        assert_eq!(category("several"), None);
+    }
+}
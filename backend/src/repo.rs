@@ -12,30 +12,224 @@ use ::error::*;
 use ::db::{self, Db, Connection};
 use ::app::App;
 use ::db::schema::*;
+use ::formats::xliff::{XliffVersion, XliffUnit, XliffDocument};
+use ::lint::LintIssue;
 
 pub type TranslationsExport = BTreeMap<String, String>;
 
+/// A value to import, given by category for plural keys.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ImportValue {
+    Single(String),
+    Plural(Vec<PluralValue>),
+}
+
+pub type TranslationsImport = BTreeMap<String, ImportValue>;
+
 pub struct Repo {
     app: App,
     db: Option<Db>,
+    /// Scopes of the api token used for the current request, if any.
+    scopes: Option<Vec<Scope>>,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum ExportFormat {
     Json,
     Javascript,
+    /// Gettext catalog, for translations.
+    Po,
+    /// Gettext template, for keys.
+    Pot,
+    /// Android `strings.xml`, for translations.
+    Android,
+    /// iOS `Localizable.strings`, for translations.
+    Ios,
+    /// iOS `Localizable.stringsdict` with the plurals, for translations.
+    Stringsdict,
+    /// Typed TypeScript modules.
+    Typescript,
+    /// A Rust module with all languages, for keys.
+    Rust,
 }
 
 impl ExportFormat {
+    /// The default bundle path of the translation file of a language, or
+    /// None if the format has no files per language.
+    fn bundle_path(&self) -> Option<&'static str> {
+        match *self {
+            ExportFormat::Json => Some("{code}.json"),
+            ExportFormat::Javascript => Some("{code}.js"),
+            ExportFormat::Po | ExportFormat::Pot => Some("{code}.po"),
+            ExportFormat::Android => Some("values-{android_code}/strings.xml"),
+            ExportFormat::Ios => Some("{code}.lproj/Localizable.strings"),
+            ExportFormat::Stringsdict => Some("{code}.lproj/Localizable.stringsdict"),
+            ExportFormat::Typescript => Some("{code}.ts"),
+            ExportFormat::Rust => None,
+        }
+    }
+
+    /// The format and default bundle path of the keys file, if there is one.
+    fn bundle_keys(&self) -> Option<(ExportFormat, &'static str)> {
+        match *self {
+            ExportFormat::Json => Some((ExportFormat::Json, "keys.json")),
+            ExportFormat::Javascript => Some((ExportFormat::Javascript, "keys.js")),
+            ExportFormat::Po | ExportFormat::Pot => Some((ExportFormat::Pot, "messages.pot")),
+            ExportFormat::Typescript => Some((ExportFormat::Typescript, "keys.ts")),
+            // The module contains all languages.
+            ExportFormat::Rust => Some((ExportFormat::Rust, "translations.rs")),
+            _ => None,
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s.trim() {
             "json" => Some(ExportFormat::Json),
             "javascript" => Some(ExportFormat::Javascript),
+            "po" => Some(ExportFormat::Po),
+            "pot" => Some(ExportFormat::Pot),
+            "android" => Some(ExportFormat::Android),
+            "ios" => Some(ExportFormat::Ios),
+            "stringsdict" => Some(ExportFormat::Stringsdict),
+            "typescript" => Some(ExportFormat::Typescript),
+            "rust" => Some(ExportFormat::Rust),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ExportOptions {
+    pub pretty: bool,
+    /// Fill missing translations from the parents of the language, then from
+    /// the default language of the project.
+    pub fallback: bool,
+    /// Export every value along with the code of the language it came from.
+    /// Only supported for json.
+    pub annotate: bool,
+    /// Export nested objects following the key hierarchy instead of a flat
+    /// map. Only supported for json and javascript.
+    pub nested: bool,
+    /// Export the last approved version of translations, leaving out those
+    /// that were never approved as if they were missing.
+    pub only_approved: bool,
+}
+
+/// A translated value and the language it was taken from.
+pub struct ExportValue {
+    pub key: Key,
+    /// The other form for plural keys.
+    pub value: String,
+    /// The values of plural keys by category, in canonical order.
+    /// Empty for other keys.
+    pub plurals: Vec<(&'static str, String)>,
+    pub language: String,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ImportFormat {
+    Json,
+    Po,
+    /// XLIFF 1.2 or 2.0.
+    /// Only updates existing keys.
+    Xliff,
+}
+
+impl ImportFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "json" => Some(ImportFormat::Json),
+            "po" => Some(ImportFormat::Po),
+            "xliff" => Some(ImportFormat::Xliff),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ImportMode {
+    /// Only add translations that do not exist yet.
+    AddMissing,
+    /// Add missing translations and overwrite existing ones.
+    Overwrite,
+    /// Report what an overwrite would change, without changing anything.
+    DryRun,
+}
+
+impl ImportMode {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.trim() {
+            "add-missing" => Some(ImportMode::AddMissing),
+            "overwrite" => Some(ImportMode::Overwrite),
+            "dry-run" => Some(ImportMode::DryRun),
             _ => None,
         }
     }
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportError {
+    pub key: String,
+    pub message: String,
+}
+
+/// Summary of the changes made by an import, listed by key.
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Keys that did not exist and were created.
+    pub created_keys: Vec<String>,
+    /// Keys that had no translation yet.
+    pub added: Vec<String>,
+    /// Keys whose translation was changed.
+    pub updated: Vec<String>,
+    /// Keys that were left untouched.
+    pub skipped: Vec<String>,
+    /// Keys that do not exist and were not created.
+    pub unknown_keys: Vec<String>,
+    /// Keys that were not imported because the source text changed since the
+    /// file was exported.
+    pub conflicts: Vec<ImportError>,
+    /// Keys that could not be imported.
+    pub errors: Vec<ImportError>,
+    /// Keys that were imported with values that do not match the source
+    /// language, like missing placeholders.
+    pub warnings: Vec<ImportError>,
+}
+
+impl ImportReport {
+    fn new(dry_run: bool) -> Self {
+        ImportReport {
+            dry_run,
+            created_keys: Vec::new(),
+            added: Vec::new(),
+            updated: Vec::new(),
+            skipped: Vec::new(),
+            unknown_keys: Vec::new(),
+            conflicts: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn error<S: Into<String>>(&mut self, key: &str, message: S) {
+        self.errors.push(ImportError {
+            key: key.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+/// A translation that does not match the value of its key in the source
+/// language.
+#[derive(Debug, Clone)]
+pub struct TranslationLint {
+    pub key: Key,
+    pub translation: Translation,
+    pub source: String,
+    pub issues: Vec<LintIssue>,
+}
+
 type Tree = Rc<RefCell<BTreeMap<String, MutableKeyTree>>>;
 
 #[derive(Debug, Clone)]
@@ -82,9 +276,11 @@ impl MutableKeyTree {
         return self.get(name).is_some()
     }
 
-    fn insert_nested(&mut self, key: String, mut parts: Vec<String>) {
+    fn insert_nested(&mut self, key: String, mut parts: Vec<String>) -> Result<()> {
         let node = match *self {
-            MutableKeyTree::Key(_) => { panic!("Can't insert into a key"); },
+            MutableKeyTree::Key(ref k) => {
+                return Err(format!("Can't insert {} into the key {}", key, k).into());
+            },
             MutableKeyTree::Map(ref m) => m,
         };
 
@@ -93,17 +289,22 @@ impl MutableKeyTree {
 
         if parts.len() < 1 {
             // Last part, so insert as key.
-            node.borrow_mut().insert(name.clone(), MutableKeyTree::Key(key));
+            let mut map = node.borrow_mut();
+            if map.get(&name).map(|n| n.is_map()).unwrap_or(false) {
+                return Err(format!("Can't insert the key {} over nested keys", key).into());
+            }
+            map.insert(name.clone(), MutableKeyTree::Key(key));
         } else {
             let mut map = node.borrow_mut();
             let nested = map.entry(name.clone()).or_insert(MutableKeyTree::new_map());
-            nested.insert_nested(key, parts);
+            nested.insert_nested(key, parts)?;
         }
+        Ok(())
     }
 
-    pub fn insert(&mut self, key: String) {
+    pub fn insert(&mut self, key: String) -> Result<()> {
         let parts = key.split('.').map(|x| x.to_string()).collect();
-        self.insert_nested(key, parts);
+        self.insert_nested(key, parts)
     }
 
     pub fn to_json_value(self) -> Value {
@@ -121,6 +322,28 @@ impl MutableKeyTree {
             },
         }
     }
+
+    /// Convert into nested json with every key replaced by its value.
+    /// Keys without a value are left out, and so are maps that end up empty.
+    pub fn to_json_values(self, values: &mut BTreeMap<String, Value>) -> Option<Value> {
+        match self {
+            MutableKeyTree::Key(s) => values.remove(&s),
+            MutableKeyTree::Map(tree) => {
+                let tree = Rc::try_unwrap(tree).unwrap().into_inner();
+
+                let mut map = json!({});
+                let mut empty = true;
+                for (key, val) in tree.into_iter() {
+                    if let Some(val) = val.to_json_values(values) {
+                        map[key] = val;
+                        empty = false;
+                    }
+                }
+
+                if empty { None } else { Some(map) }
+            },
+        }
+    }
 }
 
 impl Repo {
@@ -128,9 +351,16 @@ impl Repo {
         Repo {
             app,
             db: None,
+            scopes: None,
         }
     }
 
+    /// Restrict all operations to the scopes of the token, if it has any.
+    pub fn with_token(mut self, token: Option<&ApiToken>) -> Self {
+        self.scopes = token.and_then(|t| t.scope_list());
+        self
+    }
+
     pub fn db(&mut self) -> Result<&Db> {
         if self.db.is_none() {
             self.db = Some(self.app.db()?);
@@ -138,6 +368,20 @@ impl Repo {
         Ok(self.db.as_ref().unwrap())
     }
 
+    /// Run `f` within a database transaction, which is rolled back if `f`
+    /// fails. Transactions may be nested.
+    fn transaction<T, F>(&mut self, f: F) -> Result<T>
+        where F: FnOnce(&mut Self) -> Result<T>
+    {
+        self.db()?.begin_transaction()?;
+        let res = f(self);
+        match res {
+            Ok(_) => self.db()?.commit_transaction()?,
+            Err(_) => self.db()?.rollback_transaction()?,
+        }
+        res
+    }
+
     pub fn ensure_admin_user(&mut self) -> Result<()> {
         let db = self.db()?;
         // Ensure admin user exists.
@@ -151,6 +395,8 @@ impl Repo {
 
     pub fn login<S: AsRef<str>>(&mut self, username: S, password: S) -> Result<ApiToken> {
         let admin_pw = self.app.config().admin_password.clone();
+        let secret = self.app.config().secret.clone();
+        let lifetime = self.app.config().session_lifetime;
         let db = self.db()?;
 
         let username = username.as_ref();
@@ -171,26 +417,244 @@ impl Repo {
             }
         }
 
-        let token = user.build_session_token()?;
+        let token = user.build_session_token(&secret, lifetime)?;
         db.create_api_token(token)
     }
 
+    /// Revoke a session or api token.
+    pub fn logout(&mut self, token: &ApiToken) -> Result<()> {
+        self.db()?.delete_api_token(&token.token)
+    }
+
+    /// Resolve the user that owns a session or api token.
+    /// Session tokens must additionally carry a valid signature.
+    pub fn authenticate(&mut self, token: &str) -> Result<(User, ApiToken)> {
+        let secret = self.app.config().secret.clone();
+        let db = self.db()?;
+
+        let token = match db.api_token(token)? {
+            Some(t) => t,
+            None => {
+                return Err(ErrorKind::InvalidToken.into());
+            },
+        };
+
+        if TokenKind::from_str(&token.kind) == Some(TokenKind::Session) {
+            ::simple_jwt::decode(&token.token, &secret)
+                .map_err(|_| Error::from(ErrorKind::InvalidToken))?;
+        }
+
+        if let Some(expires_at) = token.expires_at {
+            if expires_at < Utc::now().timestamp() {
+                return Err(ErrorKind::TokenExpired.into());
+            }
+        }
+
+        let user = match token.created_by {
+            Some(ref id) => db.user_by_id(id)?,
+            None => {
+                return Err(ErrorKind::InvalidToken.into());
+            },
+        };
+
+        match user {
+            Some(u) => Ok((u, token)),
+            None => Err(ErrorKind::UnknownUser.into()),
+        }
+    }
+
+
+    /// Ensure that a user is allowed to perform an operation.
+    /// Anonymous requests are denied.
+    fn authorize(&self, user: Option<&User>, perm: Permission) -> Result<()> {
+        if let Some(ref scopes) = self.scopes {
+            if !scopes.iter().any(|s| s.grants(perm)) {
+                return Err(ErrorKind::PermissionDenied.into());
+            }
+        }
+
+        match user {
+            Some(u) if u.has_permission(perm) => Ok(()),
+            _ => Err(ErrorKind::PermissionDenied.into()),
+        }
+    }
+
+    /// Create a long-lived api token for the current user.
+    pub fn create_api_token(&mut self, name: String, scopes: Vec<String>, expires_at: Option<i64>, user: Option<&User>)
+        -> Result<ApiToken>
+    {
+        let user = match user {
+            // Api tokens may not be used to create further tokens.
+            Some(u) if self.scopes.is_none() => u,
+            _ => {
+                return Err(ErrorKind::PermissionDenied.into());
+            },
+        };
+
+        let name = name.trim().to_string();
+        if name == "" {
+            return Err("Token name may not be empty".into());
+        }
+
+        if scopes.len() < 1 {
+            return Err("At least one scope is required".into());
+        }
+        let mut scope_names = Vec::new();
+        for scope in scopes {
+            match Scope::from_str(scope.trim()) {
+                Some(s) => scope_names.push(s.to_str()),
+                None => {
+                    return Err(ErrorKind::InvalidScope(scope).into());
+                },
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        if expires_at.map(|e| e <= now).unwrap_or(false) {
+            return Err("Expiry date must be in the future".into());
+        }
+
+        let token = ApiToken {
+            token: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+            kind: TokenKind::Api.to_str().to_string(),
+            created_at: now,
+            expires_at,
+            created_by: Some(user.id.clone()),
+            name: Some(name),
+            scopes: Some(scope_names.join(" ")),
+            id: Uuid::new_v4().to_string(),
+        };
+        self.db()?.create_api_token(token)
+    }
+
+    /// List the api tokens of the current user, without their secrets.
+    pub fn api_tokens(&mut self, user: Option<&User>) -> Result<Vec<ApiTokenInfo>> {
+        let user_id = match user {
+            Some(u) if self.scopes.is_none() => u.id.clone(),
+            _ => {
+                return Err(ErrorKind::PermissionDenied.into());
+            },
+        };
+        let tokens = self.db()?.api_tokens_by_user(&user_id, TokenKind::Api)?;
+        Ok(tokens.into_iter().map(ApiTokenInfo).collect())
+    }
+
+    /// Revoke an api token by its id.
+    /// Users may revoke their own tokens, admins may revoke all tokens.
+    pub fn revoke_api_token(&mut self, id: &str, user: Option<&User>) -> Result<()> {
+        let user = match user {
+            Some(u) if self.scopes.is_none() => u,
+            _ => {
+                return Err(ErrorKind::PermissionDenied.into());
+            },
+        };
+
+        let db = self.db()?;
+        let token = match db.api_token_by_id(id)? {
+            Some(t) => t,
+            None => {
+                return Err(ErrorKind::InvalidToken.into());
+            },
+        };
+
+        let is_owner = token.created_by.as_ref() == Some(&user.id);
+        if !is_owner && !user.has_permission(Permission::ManageUsers) {
+            return Err(ErrorKind::PermissionDenied.into());
+        }
+        db.delete_api_token(&token.token)
+    }
+
+    /// Ensure that a user may perform an operation within a project.
+    /// Users must be members of the project, unless they may manage all projects.
+    fn authorize_project(&mut self, project_id: &str, user: Option<&User>, perm: Permission) -> Result<()> {
+        self.authorize(user, perm)?;
+
+        let user = match user {
+            Some(u) => u,
+            None => {
+                return Err(ErrorKind::PermissionDenied.into());
+            },
+        };
+        if user.has_permission(Permission::ManageProjects) {
+            return Ok(());
+        }
+        if self.db()?.project_member(project_id, &user.id)?.is_none() {
+            return Err(ErrorKind::PermissionDenied.into());
+        }
+        Ok(())
+    }
 
-    pub fn translations_export(&mut self, lang_id: String, format: ExportFormat, pretty: bool)
+    pub fn translations_export(&mut self, project_id: &str, lang_id: &str, format: ExportFormat, options: ExportOptions, user: Option<&User>)
         -> Result<String>
     {
-        // Load all translations for the specified language.
-        let translations = self.db()?.translations_with_keys(&lang_id)?;
+        self.authorize_project(project_id, user, Permission::Export)?;
+        let lang = self.must_get_project_language(project_id, lang_id)?;
+
+        if options.annotate && format != ExportFormat::Json {
+            return Err("Annotations are only supported for json exports".into());
+        }
+        if options.nested && format != ExportFormat::Json && format != ExportFormat::Javascript {
+            return Err("Nested exports are only supported for json and javascript".into());
+        }
+
+        match format {
+            ExportFormat::Json | ExportFormat::Javascript => {},
+            ExportFormat::Po => {
+                return self.po_export(project_id, Some(&lang), options);
+            },
+            ExportFormat::Pot | ExportFormat::Rust => {
+                return Err("This format is only supported for key exports".into());
+            },
+            ExportFormat::Android | ExportFormat::Ios | ExportFormat::Stringsdict => {
+                return self.mobile_export(&lang, format, options);
+            },
+            ExportFormat::Typescript => {
+                let values = self.export_values(&lang, options)?;
+                return Ok(::formats::typescript::write_translations(&lang.code, &values));
+            },
+        }
 
-        let mut export = TranslationsExport::new();
-        for (t, k) in translations {
-            export.insert(k.key, t.value);
+        let values = self.export_values(&lang, options)?;
+
+        let mut export: BTreeMap<String, Value> = BTreeMap::new();
+        for v in values {
+            // Plurals are objects with a value per category.
+            let value = if v.plurals.is_empty() {
+                json!(v.value)
+            } else {
+                let mut forms = serde_json::Map::new();
+                for (category, value) in v.plurals {
+                    forms.insert(category.to_string(), json!(value));
+                }
+                Value::Object(forms)
+            };
+            let value = if options.annotate {
+                json!({
+                    "value": value,
+                    "language": v.language,
+                })
+            } else {
+                value
+            };
+            export.insert(v.key.key, value);
         }
 
-        let mut json = if pretty {
-            serde_json::to_string_pretty(&export)?
+        let data = if options.nested {
+            // Built from the translated keys only, so untranslated branches
+            // are left out.
+            let mut tree = MutableKeyTree::new_map();
+            for key in export.keys() {
+                tree.insert(key.clone())?;
+            }
+            tree.to_json_values(&mut export).unwrap_or(json!({}))
+        } else {
+            to_value(export)?
+        };
+
+        let mut json = if options.pretty {
+            serde_json::to_string_pretty(&data)?
         } else {
-            serde_json::to_string(&export)?
+            serde_json::to_string(&data)?
         };
 
         if format == ExportFormat::Javascript {
@@ -202,10 +666,31 @@ impl Repo {
         Ok(json)
     }
 
-    pub fn keys_export(&mut self, format: ExportFormat, pretty: bool) -> Result<String> {
-        let tree = self.build_key_tree()?;
+    pub fn keys_export(&mut self, project_id: &str, format: ExportFormat, options: ExportOptions, user: Option<&User>)
+        -> Result<String>
+    {
+        self.must_get_project(project_id)?;
+        self.authorize_project(project_id, user, Permission::Export)?;
+
+        match format {
+            ExportFormat::Json | ExportFormat::Javascript => {},
+            ExportFormat::Pot => {
+                return self.po_export(project_id, None, ExportOptions::default());
+            },
+            ExportFormat::Typescript => {
+                return self.typescript_keys_export(project_id);
+            },
+            ExportFormat::Rust => {
+                return self.rust_export(project_id, options.only_approved);
+            },
+            ExportFormat::Po | ExportFormat::Android | ExportFormat::Ios | ExportFormat::Stringsdict => {
+                return Err("This format is only supported for translation exports".into());
+            },
+        }
+
+        let tree = self.build_key_tree(project_id)?;
         let data = tree.to_json_value();
-        let mut json = if pretty {
+        let mut json = if options.pretty {
             serde_json::to_string_pretty(&data)?
         } else {
             serde_json::to_string(&data)?
@@ -219,139 +704,1295 @@ impl Repo {
         Ok(json)
     }
 
-    pub fn build_key_tree(&mut self) -> Result<MutableKeyTree> {
-        let keys = self.db()?.keys()?;
-        let mut t = MutableKeyTree::new_map();
+    /// The parents of a language, starting with the language itself, followed
+    /// by the default language of the project.
+    fn fallback_chain(&mut self, lang: &Language) -> Result<Vec<Language>> {
+        let mut chain = vec![lang.clone()];
+        let mut parent_id = lang.parent_id.clone();
 
-        for key in keys {
-            t.insert(key.key);
+        while let Some(id) = parent_id {
+            if let Some(repeated) = chain.iter().find(|l| l.id == id) {
+                let codes: Vec<_> = chain.iter().map(|l| l.code.as_str()).collect();
+                return Err(format!("Language parents form a cycle: {} -> {}", codes.join(" -> "), repeated.code).into());
+            }
+            let parent = self.must_get_language(&id)?;
+            parent_id = parent.parent_id.clone();
+            chain.push(parent);
         }
-        Ok(t)
-    }
 
-    pub fn languages(&mut self, user: Option<&User>) -> Result<Vec<Language>> {
-        self.db()?.languages()
+        let settings = self.db()?.project_settings(&lang.project_id)?;
+        if let Some(id) = settings.default_language_id {
+            if !chain.iter().any(|l| l.id == id) {
+                chain.push(self.must_get_language(&id)?);
+            }
+        }
+        Ok(chain)
     }
 
-    pub fn language(&mut self, id: &str, user: Option<&User>) -> Result<Option<Language>> {
-        self.db()?.language_by_id(id)
-    }
+    /// Load the values of all translated keys of a language, sorted by key.
+    /// Empty values count as missing for fallbacks.
+    pub fn export_values(&mut self, lang: &Language, options: ExportOptions) -> Result<Vec<ExportValue>> {
+        let chain = if options.fallback {
+            self.fallback_chain(lang)?
+        } else {
+            vec![lang.clone()]
+        };
 
-    pub fn validate_key(&mut self, key: &str) -> Result<()> {
-        if !key::validate_key(key) {
-            return Err("Invalid key format".into());
-        }
+        let mut values: BTreeMap<String, ExportValue> = BTreeMap::new();
+        for (index, l) in chain.into_iter().enumerate() {
+            // Plural forms by translation.
+            let mut forms: BTreeMap<String, Vec<(&'static str, String)>> = BTreeMap::new();
+            for f in self.db()?.plural_forms_by_lang(&l.id)? {
+                if let Some(category) = ::plurals::category(&f.category) {
+                    forms.entry(f.translation_id).or_insert(Vec::new()).push((category, f.value));
+                }
+            }
 
-        let mut tree = self.build_key_tree()?;
-        let mut parts: Vec<_> = key.split('.').collect();
+            for (t, k) in self.db()?.translations_with_keys(&l.id)? {
+                let missing = match values.get(&k.key) {
+                    Some(v) => v.value == "",
+                    None => true,
+                };
+                if !missing {
+                    continue;
+                }
 
-        while parts.len() > 0 {
-            let part = parts.remove(0);
+                // Translations that are not approved export their last approved
+                // version instead.
+                let (value, mut plurals) = if options.only_approved && t.status() != TranslationStatus::Approved {
+                    let revision = match t.approved_version {
+                        Some(version) => self.db()?.translation_revision(&t.id, version)?,
+                        None => None,
+                    };
+                    let revision = match revision {
+                        Some(r) => r,
+                        None => continue,
+                    };
+                    let plurals: BTreeMap<String, String> = match revision.plural_values {
+                        Some(ref v) => serde_json::from_str(v)?,
+                        None => BTreeMap::new(),
+                    };
+                    let plurals = plurals.into_iter()
+                        .filter_map(|(c, v)| ::plurals::category(&c).map(|c| (c, v)))
+                        .collect();
+                    (revision.value, plurals)
+                } else {
+                    let plurals = forms.remove(&t.id).unwrap_or(Vec::new());
+                    (t.value, plurals)
+                };
+                if index > 0 && value == "" {
+                    continue;
+                }
 
-            match tree.get(&part) {
-                Some(subtree) => {
-                    if parts.len() == 0 {
-                        if subtree.is_key() {
-                            return Err("Duplicate key".into());
-                        } else {
-                            return Err("Invalid nested key: can't create a key inside an existing hierarchy".into());
-                        }
-                    } else if subtree.is_key() {
-                        return Err("Invalid nested key: can't create a key under an existing key".into());
-                    } else {
-                        tree = subtree;
+                if k.plural {
+                    if plurals.is_empty() {
+                        plurals.push(("other", value.clone()));
                     }
-                },
-                None => {
-                    return Ok(())
-                },
+                    plurals.sort_by_key(|f| ::plurals::CATEGORIES.iter().position(|c| *c == f.0));
+                } else {
+                    plurals = Vec::new();
+                }
+                values.insert(k.key.clone(), ExportValue {
+                    key: k,
+                    value,
+                    plurals,
+                    language: l.code.clone(),
+                });
             }
-
-            let subtree = tree.get(&part);
         }
-
-        Ok(())
+        Ok(values.into_iter().map(|(_, v)| v).collect())
     }
 
-    pub fn create_language(&mut self, lang: NewLanguage, user: Option<&User>) -> Result<Language> {
-        let lang = Language {
-            id: Uuid::new_v4().to_string(),
-            code: lang.code,
-            name: lang.name,
-            parent_id: lang.parent_id,
-            created_by: user.map(|u| u.id.clone()),
-            created_at: Utc::now().timestamp(),
+    /// Build the typed keys module of a project.
+    /// Parameters are taken from the values of the default language.
+    fn typescript_keys_export(&mut self, project_id: &str) -> Result<String> {
+        let settings = self.db()?.project_settings(project_id)?;
+        let mut sources: BTreeMap<String, String> = match settings.default_language_id {
+            Some(id) => {
+                self.db()?.translations_with_keys(&id)?
+                    .into_iter()
+                    .map(|(t, k)| (k.id, t.value))
+                    .collect()
+            },
+            None => BTreeMap::new(),
         };
-        eprintln!("Creating lang: {:?}", lang);
-        self.db()?.create_language(lang)
-    }
 
-    pub fn delete_language<S: AsRef<str>>(&mut self, language: S, user: Option<&User>) -> Result<()> {
-        self.db()?.delete_language(language.as_ref())?;
-        Ok(())
-    }
+        let mut keys = self.db()?.keys(project_id)?;
+        keys.sort_by(|a, b| a.key.cmp(&b.key));
+        let keys: Vec<_> = keys.into_iter().map(|k| {
+            let params = sources.remove(&k.id)
+                .map(|v| ::formats::params::extract_params(&v))
+                .unwrap_or(Vec::new());
+            (k.key, params)
+        }).collect();
 
-    pub fn keys(&mut self, user: Option<&User>) -> Result<Vec<Key>> {
-        self.db()?.keys()
+        Ok(::formats::typescript::write_keys(&keys))
     }
 
-    pub fn key_by_id(&mut self, id: &str, user: Option<&User>) -> Result<Option<Key>> {
-        self.db()?.key_by_id(id)
-    }
+    /// Build a Rust module with the translations of all languages.
+    /// Missing translations are filled from fallback languages, and
+    /// parameters are taken from the values of the default language.
+    fn rust_export(&mut self, project_id: &str, only_approved: bool) -> Result<String> {
+        let project = self.must_get_project(project_id)?;
+        let settings = self.db()?.project_settings(project_id)?;
 
-    pub fn key_by_key(&mut self, key: &str, user: Option<&User>) -> Result<Option<Key>> {
-        self.db()?.key_by_key(key)
-    }
+        let tree = self.build_key_tree(project_id)?;
+        let mut keys: Vec<String> = self.db()?.keys(project_id)?.into_iter().map(|k| k.key).collect();
+        keys.sort();
 
-    pub fn create_key(&mut self, key: NewKey, user: Option<&User>) -> Result<Key> {
-        self.validate_key(&key.key)?;
-        let key = Key{
-            id: Uuid::new_v4().to_string(),
-            key: key.key,
-            description: key.description,
-            created_at: Utc::now().timestamp(),
-            created_by: user.map(|u| u.username.clone()),
+        let mut languages = self.db()?.languages(project_id)?;
+        languages.sort_by(|a, b| a.code.cmp(&b.code));
+
+        let options = ExportOptions {
+            fallback: true,
+            only_approved,
+            ..ExportOptions::default()
         };
-        self.db()?.create_key(key)
-    }
+        let mut params = BTreeMap::new();
+        let mut rust_languages = Vec::new();
+        for lang in languages {
+            let mut values = BTreeMap::new();
+            let mut plurals = BTreeMap::new();
+            for v in self.export_values(&lang, options)? {
+                if !v.plurals.is_empty() {
+                    plurals.insert(v.key.key.clone(), v.plurals);
+                }
+                values.insert(v.key.key, v.value);
+            }
+            if settings.default_language_id.as_ref() == Some(&lang.id) {
+                for (key, value) in values.iter() {
+                    params.insert(key.clone(), ::formats::params::extract_params(value));
+                }
+            }
+            rust_languages.push(::formats::rust::RustLanguage {
+                code: lang.code,
+                values,
+                plurals,
+            });
+        }
 
-    pub fn rename_key(&mut self, id: &str, new_key: &str, user: Option<&User>) -> Result<Key> {
-        self.validate_key(new_key)?;
-        let db = self.db()?;
-        db.rename_key(id, new_key)?;
-        db.must_get_key(id)
+        ::formats::rust::write_module(&project.name, &tree, &keys, &params, &rust_languages)
     }
 
-    pub fn delete_key<S: AsRef<str>>(&mut self, key: S, user: Option<&User>) -> Result<()> {
-        self.db()?.delete_key(key.as_ref())?;
-        Ok(())
+    /// Build a gettext catalog with all keys of a project.
+    /// Without a language, a template is built.
+    fn po_export(&mut self, project_id: &str, lang: Option<&Language>, options: ExportOptions) -> Result<String> {
+        let project = self.must_get_project(project_id)?;
+        let mut values: BTreeMap<String, ExportValue> = match lang {
+            Some(l) => {
+                self.export_values(l, options)?
+                    .into_iter()
+                    .map(|v| (v.key.id.clone(), v))
+                    .collect()
+            },
+            None => BTreeMap::new(),
+        };
+        let categories: &[&str] = match lang {
+            Some(l) => ::plurals::rule(&l.code).gettext_categories,
+            None => &[],
+        };
+
+        let mut keys = self.db()?.keys(project_id)?;
+        keys.sort_by(|a, b| a.key.cmp(&b.key));
+        let entries: Vec<_> = keys.into_iter().map(|k| {
+            let value = values.remove(&k.id);
+            // Forms missing from the values of fallback languages are left
+            // empty.
+            let plurals = if k.plural {
+                Some(categories.iter().map(|c| {
+                    value.as_ref()
+                        .and_then(|v| v.plurals.iter().find(|f| f.0 == *c))
+                        .map(|f| f.1.clone())
+                        .unwrap_or(String::new())
+                }).collect())
+            } else {
+                None
+            };
+            ::formats::po::PoEntry {
+                value: value.map(|v| v.value),
+                plurals,
+                key: k.key,
+                description: k.description,
+            }
+        }).collect();
+
+        Ok(::formats::po::write_catalog(&project.name, lang.map(|l| l.code.as_str()), &entries))
     }
 
-    pub fn translations(&mut self, key_id: &str, user: Option<&User>)
-        -> Result<Vec<Translation>>
+    /// Build a zip archive with the translations of every language of a
+    /// project, plus the keys file if the format has one. Rust bundles only
+    /// contain the generated module, as it covers all languages. iOS bundles
+    /// add a `.stringsdict` with the plurals next to every `.strings` file.
+    /// An empty `keys_path` leaves out the keys file.
+    /// The archive is built in memory, which is fine for the size of
+    /// translation files but means bundles are not streamed.
+    pub fn bundle_export(&mut self, project_id: &str, format: ExportFormat, options: ExportOptions,
+                         path: Option<String>, keys_path: Option<String>, user: Option<&User>)
+        -> Result<Vec<u8>>
     {
+        self.must_get_project(project_id)?;
+        self.authorize_project(project_id, user, Permission::Export)?;
+
+        let format = if format == ExportFormat::Pot { ExportFormat::Po } else { format };
+
+        let mut files = Vec::new();
+        if let Some(default_path) = format.bundle_path() {
+            let path = path.unwrap_or(default_path.to_string());
+
+            let mut languages = self.db()?.languages(project_id)?;
+            languages.sort_by(|a, b| a.code.cmp(&b.code));
+
+            for lang in languages {
+                let lang_path = ::formats::bundle::expand_path(&path, &lang.code)?;
+                if format == ExportFormat::Ios {
+                    files.push(::formats::bundle::BundleFile {
+                        path: stringsdict_path(&lang_path),
+                        content: self.translations_export(project_id, &lang.id, ExportFormat::Stringsdict, options, user)?,
+                    });
+                }
+                files.push(::formats::bundle::BundleFile {
+                    path: lang_path,
+                    content: self.translations_export(project_id, &lang.id, format, options, user)?,
+                });
+            }
+        }
+
+        if let Some((keys_format, default_path)) = format.bundle_keys() {
+            let keys_path = keys_path.unwrap_or(default_path.to_string());
+            if keys_path.contains("{code}") || keys_path.contains("{android_code}") {
+                return Err(format!("The keys file is the same for all languages: {}", keys_path).into());
+            }
+            if keys_path != "" {
+                files.push(::formats::bundle::BundleFile {
+                    path: ::formats::bundle::expand_path(&keys_path, "")?,
+                    content: self.keys_export(project_id, keys_format, options, user)?,
+                });
+            }
+        }
+
+        let mut paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        if let Some(w) = paths.windows(2).find(|w| w[0] == w[1]) {
+            return Err(format!("Several files would be written to {}", w[0]).into());
+        }
+
+        ::formats::bundle::write_zip(&files)
+    }
+
+    /// Build Android or iOS resources with all translated keys of a language.
+    fn mobile_export(&mut self, lang: &Language, format: ExportFormat, options: ExportOptions) -> Result<String> {
+        let values = self.export_values(lang, options)?
+            .into_iter()
+            .filter(|v| v.value != "")
+            .map(|v| {
+                let value = if v.plurals.is_empty() {
+                    ::formats::mobile::MobileValue::Single(v.value)
+                } else {
+                    ::formats::mobile::MobileValue::Plural(v.plurals)
+                };
+                (v.key.key, v.key.description, value)
+            })
+            .collect();
+        let entries = ::formats::mobile::build_entries(values)?;
+
+        Ok(match format {
+            ExportFormat::Android => ::formats::android::write_resources(&entries),
+            ExportFormat::Ios => ::formats::ios::write_strings(&entries),
+            _ => ::formats::ios::write_stringsdict(&entries),
+        })
+    }
+
+    /// Build an XLIFF document for translating from one language into another.
+    /// Keys without a source text are left out.
+    pub fn xliff_export(&mut self, project_id: &str, source_id: &str, target_id: &str, version: XliffVersion, user: Option<&User>)
+        -> Result<String>
+    {
+        let project = self.must_get_project(project_id)?;
+        let source = self.must_get_project_language(project_id, source_id)?;
+        let target = self.must_get_project_language(project_id, target_id)?;
+        self.authorize_project(project_id, user, Permission::Export)?;
+
+        let mut sources: BTreeMap<String, String> = self.db()?.translations_with_keys(&source.id)?
+            .into_iter()
+            .map(|(t, k)| (k.id, t.value))
+            .collect();
+        let mut targets: BTreeMap<String, String> = self.db()?.translations_with_keys(&target.id)?
+            .into_iter()
+            .map(|(t, k)| (k.id, t.value))
+            .collect();
+
+        let mut keys = self.db()?.keys(project_id)?;
+        keys.sort_by(|a, b| a.key.cmp(&b.key));
+        let units = keys.into_iter().filter_map(|k| {
+            let source = match sources.remove(&k.id) {
+                Some(ref s) if s == "" => return None,
+                Some(s) => s,
+                None => return None,
+            };
+            Some(XliffUnit {
+                source,
+                target: targets.remove(&k.id),
+                note: k.description,
+                id: k.key,
+            })
+        }).collect();
+
+        let doc = XliffDocument {
+            source_language: Some(source.code),
+            target_language: Some(target.code),
+            units,
+        };
+        Ok(::formats::xliff::write_document(version, &project.name, &doc))
+    }
+
+    pub fn build_key_tree(&mut self, project_id: &str) -> Result<MutableKeyTree> {
+        let keys = self.db()?.keys(project_id)?;
+        let mut t = MutableKeyTree::new_map();
+
+        for key in keys {
+            t.insert(key.key)?;
+        }
+        Ok(t)
+    }
+
+    fn must_get_project(&mut self, id: &str) -> Result<Project> {
+        match self.db()?.project_by_id(id)? {
+            Some(p) => Ok(p),
+            None => Err(ErrorKind::UnknownProject.into()),
+        }
+    }
+
+    /// Load the projects visible to the user.
+    pub fn projects(&mut self, user: Option<&User>) -> Result<Vec<Project>> {
+        self.authorize(user, Permission::Read)?;
+        let user = user.unwrap();
+
+        if user.has_permission(Permission::ManageProjects) {
+            self.db()?.projects()
+        } else {
+            self.db()?.projects_by_member(&user.id)
+        }
+    }
+
+    pub fn project(&mut self, id: &str, user: Option<&User>) -> Result<Option<Project>> {
+        let project = match self.db()?.project_by_id(id)? {
+            Some(p) => p,
+            None => {
+                return Ok(None);
+            },
+        };
+        self.authorize_project(&project.id, user, Permission::Read)?;
+        Ok(Some(project))
+    }
+
+    pub fn create_project(&mut self, project: NewProject, user: Option<&User>) -> Result<Project> {
+        self.authorize(user, Permission::ManageProjects)?;
+
+        let name = project.name.trim().to_string();
+        if name == "" {
+            return Err("Project name may not be empty".into());
+        }
+
+        let now = Utc::now().timestamp();
+        let project = Project {
+            id: Uuid::new_v4().to_string(),
+            name,
+            created_at: now,
+            created_by: user.map(|u| u.id.clone()),
+        };
+        let project = self.db()?.create_project(project)?;
+
+        if let Some(u) = user {
+            self.db()?.add_project_member(ProjectMember {
+                project_id: project.id.clone(),
+                user_id: u.id.clone(),
+                created_at: now,
+            })?;
+        }
+        Ok(project)
+    }
+
+    pub fn delete_project(&mut self, id: &str, user: Option<&User>) -> Result<()> {
+        self.authorize(user, Permission::ManageProjects)?;
+        self.must_get_project(id)?;
+        self.db()?.delete_project(id)
+    }
+
+    pub fn project_members(&mut self, project_id: &str, user: Option<&User>) -> Result<Vec<User>> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.project_users(project_id)
+    }
+
+    pub fn project_settings(&mut self, project_id: &str, user: Option<&User>) -> Result<ProjectSettings> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.project_settings(project_id)
+    }
+
+    /// Set the language used for fallbacks after the parents of a language.
+    /// The language can be given by id or code.
+    pub fn set_default_language(&mut self, project_id: &str, language: Option<&str>, user: Option<&User>)
+        -> Result<ProjectSettings>
+    {
+        self.must_get_project(project_id)?;
+        self.authorize_project(project_id, user, Permission::ManageLanguages)?;
+
+        let mut settings = self.db()?.project_settings(project_id)?;
+        settings.default_language_id = match language {
+            Some(l) => Some(self.must_get_project_language(project_id, l)?.id),
+            None => None,
+        };
+        self.save_settings(&settings)?;
+        Ok(settings)
+    }
+
+    /// Set the language translations are made from, instead of the default
+    /// language. The language can be given by id or code.
+    pub fn set_source_language(&mut self, project_id: &str, language: Option<&str>, user: Option<&User>)
+        -> Result<ProjectSettings>
+    {
+        self.must_get_project(project_id)?;
+        self.authorize_project(project_id, user, Permission::ManageLanguages)?;
+
+        let mut settings = self.db()?.project_settings(project_id)?;
+        settings.source_language_id = match language {
+            Some(l) => Some(self.must_get_project_language(project_id, l)?.id),
+            None => None,
+        };
+        self.save_settings(&settings)?;
+        Ok(settings)
+    }
+
+    /// Save the settings of a project. Changing the source language bases
+    /// the translations of the project on the values of the new one.
+    fn save_settings(&mut self, settings: &ProjectSettings) -> Result<()> {
+        let old = self.db()?.project_settings(&settings.project_id)?;
+        let source = project_source_language(settings).cloned();
+        self.transaction(|repo| {
+            repo.db()?.save_project_settings(settings)?;
+            if project_source_language(&old) != source.as_ref() {
+                repo.db()?.reset_source_versions(&settings.project_id, source.as_ref().map(|s| s.as_str()))?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn set_placeholder_syntax(&mut self, project_id: &str, syntax: &str, user: Option<&User>)
+        -> Result<ProjectSettings>
+    {
+        self.must_get_project(project_id)?;
+        self.authorize_project(project_id, user, Permission::ManageLanguages)?;
+
+        let syntax = match PlaceholderSyntax::from_str(syntax.trim()) {
+            Some(s) => s,
+            None => {
+                return Err(format!("Unknown placeholder syntax {}", syntax).into());
+            },
+        };
+        let mut settings = self.db()?.project_settings(project_id)?;
+        settings.placeholder_syntax = syntax.to_str().to_string();
+        self.db()?.save_project_settings(&settings)?;
+        Ok(settings)
+    }
+
+    pub fn add_project_member(&mut self, project_id: &str, user_id: &str, user: Option<&User>) -> Result<()> {
+        self.authorize(user, Permission::ManageProjects)?;
+        self.must_get_project(project_id)?;
+        self.must_get_user(user_id)?;
+
+        let db = self.db()?;
+        if db.project_member(project_id, user_id)?.is_none() {
+            db.add_project_member(ProjectMember {
+                project_id: project_id.to_string(),
+                user_id: user_id.to_string(),
+                created_at: Utc::now().timestamp(),
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn remove_project_member(&mut self, project_id: &str, user_id: &str, user: Option<&User>) -> Result<()> {
+        self.authorize(user, Permission::ManageProjects)?;
+        self.db()?.remove_project_member(project_id, user_id)
+    }
+
+    fn must_get_language(&mut self, id: &str) -> Result<Language> {
+        match self.db()?.language_by_id(id)? {
+            Some(l) => Ok(l),
+            None => Err(ErrorKind::UnknownLanguage.into()),
+        }
+    }
+
+    /// Find a language of a project by id or by code.
+    fn must_get_project_language(&mut self, project_id: &str, id_or_code: &str) -> Result<Language> {
+        if id_or_code.parse::<Uuid>().is_ok() {
+            if let Some(lang) = self.db()?.language_by_id(id_or_code)? {
+                if lang.project_id == project_id {
+                    return Ok(lang);
+                }
+            }
+        }
+        match self.db()?.language_by_code(project_id, id_or_code)? {
+            Some(l) => Ok(l),
+            None => Err(ErrorKind::UnknownLanguage.into()),
+        }
+    }
+
+    pub fn languages(&mut self, project_id: &str, user: Option<&User>) -> Result<Vec<Language>> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.languages(project_id)
+    }
+
+    pub fn language(&mut self, id: &str, user: Option<&User>) -> Result<Option<Language>> {
+        let lang = match self.db()?.language_by_id(id)? {
+            Some(l) => l,
+            None => {
+                return Ok(None);
+            },
+        };
+        self.authorize_project(&lang.project_id, user, Permission::Read)?;
+        Ok(Some(lang))
+    }
+
+    pub fn language_by_code(&mut self, project_id: &str, code: &str, user: Option<&User>) -> Result<Option<Language>> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.language_by_code(project_id, code)
+    }
+
+    /// Find a language of a project by id or by code.
+    pub fn project_language(&mut self, project_id: &str, id_or_code: &str, user: Option<&User>) -> Result<Language> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.must_get_project_language(project_id, id_or_code)
+    }
+
+    pub fn validate_key(&mut self, project_id: &str, key: &str) -> Result<()> {
+        if !key::validate_key(key) {
+            return Err("Invalid key format".into());
+        }
+
+        let mut tree = self.build_key_tree(project_id)?;
+        let mut parts: Vec<_> = key.split('.').collect();
+
+        while parts.len() > 0 {
+            let part = parts.remove(0);
+
+            match tree.get(&part) {
+                Some(subtree) => {
+                    if parts.len() == 0 {
+                        if subtree.is_key() {
+                            return Err("Duplicate key".into());
+                        } else {
+                            return Err("Invalid nested key: can't create a key inside an existing hierarchy".into());
+                        }
+                    } else if subtree.is_key() {
+                        return Err("Invalid nested key: can't create a key under an existing key".into());
+                    } else {
+                        tree = subtree;
+                    }
+                },
+                None => {
+                    return Ok(())
+                },
+            }
+
+            let subtree = tree.get(&part);
+        }
+
+        Ok(())
+    }
+
+    pub fn create_language(&mut self, lang: NewLanguage, user: Option<&User>) -> Result<Language> {
+        let project_id = lang.project_id.unwrap_or(DEFAULT_PROJECT.to_string());
+        self.must_get_project(&project_id)?;
+        self.authorize_project(&project_id, user, Permission::ManageLanguages)?;
+
+        if let Some(ref parent_id) = lang.parent_id {
+            let parent = self.must_get_language(parent_id)?;
+            if parent.project_id != project_id {
+                return Err("Parent language belongs to a different project".into());
+            }
+        }
+
+        let lang = Language {
+            id: Uuid::new_v4().to_string(),
+            project_id,
+            code: lang.code,
+            name: lang.name,
+            parent_id: lang.parent_id,
+            created_by: user.map(|u| u.id.clone()),
+            created_at: Utc::now().timestamp(),
+        };
+        eprintln!("Creating lang: {:?}", lang);
+        self.db()?.create_language(lang)
+    }
+
+    pub fn delete_language<S: AsRef<str>>(&mut self, language: S, user: Option<&User>) -> Result<()> {
+        let lang = self.must_get_language(language.as_ref())?;
+        self.authorize_project(&lang.project_id, user, Permission::ManageLanguages)?;
+        self.db()?.delete_language(&lang.id)?;
+        Ok(())
+    }
+
+    fn must_get_key(&mut self, id: &str) -> Result<Key> {
+        match self.db()?.key_by_id(id)? {
+            Some(k) => Ok(k),
+            None => Err(ErrorKind::UnknownKey.into()),
+        }
+    }
+
+    pub fn keys(&mut self, project_id: &str, user: Option<&User>) -> Result<Vec<Key>> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.keys(project_id)
+    }
+
+    pub fn key_by_id(&mut self, id: &str, user: Option<&User>) -> Result<Option<Key>> {
+        let key = match self.db()?.key_by_id(id)? {
+            Some(k) => k,
+            None => {
+                return Ok(None);
+            },
+        };
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
+        Ok(Some(key))
+    }
+
+    pub fn key_by_key(&mut self, project_id: &str, key: &str, user: Option<&User>) -> Result<Option<Key>> {
+        self.authorize_project(project_id, user, Permission::Read)?;
+        self.db()?.key_by_key(project_id, key)
+    }
+
+    pub fn create_key(&mut self, key: NewKey, user: Option<&User>) -> Result<Key> {
+        let project_id = key.project_id.unwrap_or(DEFAULT_PROJECT.to_string());
+        self.must_get_project(&project_id)?;
+        self.authorize_project(&project_id, user, Permission::ManageKeys)?;
+        self.validate_key(&project_id, &key.key)?;
+        let key = Key{
+            id: Uuid::new_v4().to_string(),
+            project_id,
+            key: key.key,
+            description: key.description,
+            created_at: Utc::now().timestamp(),
+            created_by: user.map(|u| u.username.clone()),
+            plural: key.plural.unwrap_or(false),
+        };
+        self.db()?.create_key(key)
+    }
+
+    /// Mark a key as plural, or back as a single value.
+    /// Existing translations only have a single value when the key becomes
+    /// plural, so they are flagged as outdated until their forms are filled in.
+    /// Plural forms of existing translations are removed when the key stops
+    /// being plural.
+    pub fn set_key_plural(&mut self, id: &str, plural: bool, user: Option<&User>) -> Result<Key> {
+        let key = self.must_get_key(id)?;
+        self.authorize_project(&key.project_id, user, Permission::ManageKeys)?;
+        if key.plural != plural {
+            self.transaction(|repo| repo.db()?.set_key_plural(id, plural))?;
+        }
+        self.db()?.must_get_key(id)
+    }
+
+    pub fn rename_key(&mut self, id: &str, new_key: &str, user: Option<&User>) -> Result<Key> {
+        let key = self.must_get_key(id)?;
+        self.authorize_project(&key.project_id, user, Permission::ManageKeys)?;
+        self.validate_key(&key.project_id, new_key)?;
+        let db = self.db()?;
+        db.rename_key(id, new_key)?;
+        db.must_get_key(id)
+    }
+
+    pub fn delete_key<S: AsRef<str>>(&mut self, key: S, user: Option<&User>) -> Result<()> {
+        let key = self.must_get_key(key.as_ref())?;
+        self.authorize_project(&key.project_id, user, Permission::ManageKeys)?;
+        self.db()?.delete_key(&key.id)?;
+        Ok(())
+    }
+
+    pub fn translations(&mut self, key_id: &str, user: Option<&User>)
+        -> Result<Vec<Translation>>
+    {
+        let key = self.must_get_key(key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
         self.db()?.translations(key_id)
     }
 
-    pub fn translate(&mut self, translation: NewTranslation, user: Option<&User>)
+    /// Set the value of a translation. The translation, its revision and
+    /// plural forms are written in one transaction.
+    pub fn translate(&mut self, translation: NewTranslation, user: Option<&User>) -> Result<Translation> {
+        self.transaction(|repo| repo.write_translation(translation, user))
+    }
+
+    fn write_translation(&mut self, mut translation: NewTranslation, user: Option<&User>)
         -> Result<Translation>
     {
-        // Try to find old translation.
         let key_id = translation.key_id.to_string();
         let language_id = translation.language_id.to_string();
-        let t = self.db()?.find_translation(&key_id, &language_id)?;
 
-        if let Some(mut t) = t {
-            self.db()?.update_translation(&t.id, &translation.value)?;
+        let key = self.must_get_key(&key_id)?;
+        let lang = self.must_get_language(&language_id)?;
+        if key.project_id != lang.project_id {
+            return Err("Key and language belong to different projects".into());
+        }
+        self.authorize_project(&key.project_id, user, Permission::Translate)?;
+
+        // Try to find old translation.
+        let existing = self.db()?.find_translation(&key_id, &language_id)?;
+        if let Some(expected) = translation.expected_version {
+            let current = existing.as_ref().map(|t| t.version).unwrap_or(0);
+            if expected != current {
+                return Err(self.translation_conflict(existing)?);
+            }
+        }
+
+        let forms = plural_values(&key, &lang, &translation)?;
+        if let Some(other) = forms.iter().find(|f| f.0 == "other") {
+            translation.value = other.1.clone();
+        }
+
+        let settings = self.db()?.project_settings(&key.project_id)?;
+        let syntax = settings.syntax();
+        let source_id = source_language_id(&settings, &lang);
+        let source = match source_id {
+            Some(ref id) => self.db()?.find_translation(&key_id, id)?,
+            None => None,
+        };
+        // The current values by category.
+        let old_values: BTreeMap<String, String> = match existing {
+            Some(ref t) => {
+                let mut values: BTreeMap<String, String> = self.db()?.plural_forms(&t.id)?
+                    .into_iter()
+                    .map(|f| (f.category, f.value))
+                    .collect();
+                values.entry("other".to_string()).or_insert(t.value.clone());
+                values
+            },
+            None => BTreeMap::new(),
+        };
+        let changed = {
+            // The values to check by category.
+            let values: Vec<(&str, &str)> = if key.plural {
+                forms.iter().map(|f| (f.0, f.1.as_str())).collect()
+            } else {
+                vec![("other", translation.value.as_str())]
+            };
+
+            if syntax == PlaceholderSyntax::Icu {
+                for &(category, value) in values.iter() {
+                    // Values from before validation may use literal braces,
+                    // so only changed values have to be valid messages.
+                    if old_values.get(category).map(|v| v.as_str()) == Some(value) {
+                        continue;
+                    }
+                    if let Err(e) = ::message_format::parse(value) {
+                        return Err(ErrorKind::InvalidMessage(e).into());
+                    }
+                }
+            }
+
+            values.iter().any(|&(category, value)| old_values.get(category).map(|v| v.as_str()) != Some(value))
+        };
+
+        // Whether this changes the value of a source translation.
+        let source_changed = existing.is_some() && changed &&
+            project_source_language(&settings) == Some(&lang.id);
+
+        let source_version = source.map(|s| s.version);
+        let t = if let Some(mut t) = existing {
+            t.status = submitted_status(&translation).to_str().to_string();
+            t.review_comment = None;
+            t.reviewed_at = None;
+            t.reviewed_by = None;
             t.value = translation.value;
             t.version += 1;
-            Ok(t)
+            t.updated_at = Utc::now().timestamp();
+            t.source_version = source_version;
+            t.outdated = false;
+            // Only save over the version read above, in case the translation
+            // was changed since.
+            if !self.db()?.update_translation(&t, t.version - 1)? {
+                let current = self.db()?.find_translation(&key_id, &language_id)?;
+                return Err(self.translation_conflict(current)?);
+            }
+            t
+        } else {
+            self.create_translation(translation, source_version, user.map(|u| u.id.clone()))?
+        };
+        if source_changed {
+            self.db()?.mark_translations_outdated(&t.key_id, &t.language_id)?;
+        }
+
+        let plural_values = if key.plural {
+            let values: BTreeMap<&str, &str> = forms.iter().map(|f| (f.0, f.1.as_str())).collect();
+            Some(serde_json::to_string(&values)?)
+        } else {
+            None
+        };
+        self.db()?.create_translation_revision(&TranslationRevision {
+            translation_id: t.id.clone(),
+            version: t.version,
+            value: t.value.clone(),
+            plural_values,
+            created_at: t.updated_at,
+            created_by: user.map(|u| u.id.clone()),
+        })?;
+
+        if key.plural {
+            let forms: Vec<PluralForm> = forms.into_iter()
+                .map(|(category, value)| PluralForm {
+                    translation_id: t.id.clone(),
+                    category: category.to_string(),
+                    value,
+                })
+                .collect();
+            self.db()?.set_plural_forms(&t.id, &forms)?;
+        }
+        Ok(t)
+    }
+
+    /// The revisions of a translation, newest first.
+    pub fn translation_history(&mut self, translation_id: &str, user: Option<&User>) -> Result<Vec<TranslationRevision>> {
+        let translation = self.must_get_translation(translation_id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
+        self.db()?.translation_revisions(translation_id)
+    }
+
+    /// Compare the values of two revisions of a translation word by word.
+    /// Plural translations are compared by category.
+    pub fn translation_diff(&mut self, translation_id: &str, from: i32, to: i32, user: Option<&User>)
+        -> Result<Vec<::diff::ValueDiff>>
+    {
+        let translation = self.must_get_translation(translation_id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
+
+        let from = self.must_get_revision(translation_id, from)?;
+        let to = self.must_get_revision(translation_id, to)?;
+        if from.plural_values.is_none() && to.plural_values.is_none() {
+            return Ok(vec![::diff::ValueDiff {
+                category: None,
+                changes: ::diff::diff(&from.value, &to.value),
+            }]);
+        }
+
+        Ok(::diff::plural_diff(&from.plural_forms()?, &to.plural_forms()?))
+    }
+
+    /// Restore the value of an earlier revision, which creates a new revision.
+    pub fn revert_translation(&mut self, translation_id: &str, version: i32, user: Option<&User>) -> Result<Translation> {
+        let translation = self.must_get_translation(translation_id)?;
+        let revision = self.must_get_revision(translation_id, version)?;
+
+        let plural_values = if revision.plural_values.is_some() {
+            let values = revision.plural_forms()?
+                .into_iter()
+                .map(|f| PluralValue {
+                    category: f.category,
+                    value: f.value,
+                })
+                .collect();
+            Some(values)
+        } else {
+            None
+        };
+        self.translate(NewTranslation {
+            language_id: translation.language_id.parse::<Uuid>().chain_err(|| "Invalid language id")?,
+            key_id: translation.key_id.parse::<Uuid>().chain_err(|| "Invalid key id")?,
+            value: revision.value,
+            plural_values,
+            expected_version: None,
+            draft: None,
+        }, user)
+    }
+
+    /// Approve the current value of a translation, which lets it be exported
+    /// with only approved translations.
+    pub fn approve_translation(&mut self, translation_id: &str, user: Option<&User>) -> Result<Translation> {
+        self.review_translation(translation_id, TranslationStatus::Approved, None, user)
+    }
+
+    /// Reject the current value of a translation with the reason in `comment`.
+    pub fn reject_translation(&mut self, translation_id: &str, comment: String, user: Option<&User>) -> Result<Translation> {
+        if comment.trim() == "" {
+            return Err("A comment is required to reject a translation".into());
+        }
+        self.review_translation(translation_id, TranslationStatus::Rejected, Some(comment), user)
+    }
+
+    fn review_translation(&mut self, translation_id: &str, status: TranslationStatus, comment: Option<String>, user: Option<&User>)
+        -> Result<Translation>
+    {
+        let mut translation = self.must_get_translation(translation_id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Review)?;
+        if translation.status() == TranslationStatus::Draft {
+            return Err("Drafts can not be reviewed".into());
+        }
+
+        if status == TranslationStatus::Approved {
+            translation.approved_version = Some(translation.version);
+        } else if translation.approved_version == Some(translation.version) {
+            translation.approved_version = None;
+        }
+        translation.status = status.to_str().to_string();
+        translation.review_comment = comment;
+        translation.reviewed_at = Some(Utc::now().timestamp());
+        translation.reviewed_by = user.map(|u| u.id.clone());
+        // Don't review a value that was changed since it was read.
+        if !self.db()?.update_translation(&translation, translation.version)? {
+            let current = self.db()?.translation_by_id(translation_id)?;
+            return Err(self.translation_conflict(current)?);
+        }
+        Ok(translation)
+    }
+
+    /// The conflict error for a translation that changed, with its current
+    /// state and plural forms.
+    fn translation_conflict(&mut self, current: Option<Translation>) -> Result<Error> {
+        let (forms, author) = match current {
+            Some(ref t) => (self.db()?.plural_forms(&t.id)?, self.translation_author(t)?),
+            None => (Vec::new(), None),
+        };
+        Ok(ErrorKind::TranslationConflict(current, forms, author).into())
+    }
+
+    /// The username of the author of the current value of a translation.
+    fn translation_author(&mut self, translation: &Translation) -> Result<Option<String>> {
+        let revision = self.db()?.translation_revision(&translation.id, translation.version)?;
+        let user_id = match revision {
+            Some(r) => r.created_by,
+            None => translation.created_by.clone(),
+        };
+        Ok(match user_id {
+            Some(id) => self.db()?.user_by_id(&id)?.map(|u| u.username),
+            None => None,
+        })
+    }
+
+    fn must_get_translation(&mut self, id: &str) -> Result<Translation> {
+        match self.db()?.translation_by_id(id)? {
+            Some(t) => Ok(t),
+            None => Err(ErrorKind::UnknownTranslation.into()),
+        }
+    }
+
+    fn must_get_revision(&mut self, translation_id: &str, version: i32) -> Result<TranslationRevision> {
+        match self.db()?.translation_revision(translation_id, version)? {
+            Some(r) => Ok(r),
+            None => Err(format!("Unknown version {}", version).into()),
+        }
+    }
+
+    /// The translations of a language whose source translation changed since
+    /// they were made.
+    pub fn stale_translations(&mut self, language_id: &str, user: Option<&User>) -> Result<Vec<Translation>> {
+        let lang = self.must_get_language(language_id)?;
+        self.authorize_project(&lang.project_id, user, Permission::Read)?;
+        self.db()?.outdated_translations(&lang.id)
+    }
+
+    /// The plural forms of a translation.
+    pub fn plural_forms(&mut self, translation_id: &str, user: Option<&User>) -> Result<Vec<PluralForm>> {
+        let translation = self.must_get_translation(translation_id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
+
+        let mut forms = self.db()?.plural_forms(translation_id)?;
+        forms.sort_by_key(|f| ::plurals::CATEGORIES.iter().position(|c| *c == f.category));
+        Ok(forms)
+    }
+
+    /// Compare the values of a translation with the value of its key in the
+    /// source language.
+    pub fn translation_issues(&mut self, translation_id: &str, user: Option<&User>) -> Result<Vec<LintIssue>> {
+        let translation = self.must_get_translation(translation_id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Read)?;
+        self.lint_translation(&translation)
+    }
+
+    fn lint_translation(&mut self, translation: &Translation) -> Result<Vec<LintIssue>> {
+        let lang = self.must_get_language(&translation.language_id)?;
+        let settings = self.db()?.project_settings(&lang.project_id)?;
+        let source = match source_language_id(&settings, &lang) {
+            Some(id) => self.db()?.find_translation(&translation.key_id, &id)?,
+            None => None,
+        };
+        let source = match source {
+            Some(s) => s,
+            None => {
+                return Ok(Vec::new());
+            },
+        };
+
+        // Plural forms are compared with the same form of the source, or its
+        // other form.
+        let source_forms: BTreeMap<String, String> = self.db()?.plural_forms(&source.id)?
+            .into_iter()
+            .map(|f| (f.category, f.value))
+            .collect();
+        let mut values: Vec<(String, String)> = self.db()?.plural_forms(&translation.id)?
+            .into_iter()
+            .map(|f| (f.category, f.value))
+            .collect();
+        if values.is_empty() {
+            values.push(("other".to_string(), translation.value.clone()));
+        }
+        values.sort_by_key(|v| ::plurals::CATEGORIES.iter().position(|c| *c == v.0));
+
+        let mut issues = Vec::new();
+        for (category, value) in values {
+            let source_value = source_forms.get(&category).unwrap_or(&source.value);
+            if source_value == "" || value == "" {
+                continue;
+            }
+            for issue in ::lint::lint(settings.syntax(), source_value, &value) {
+                if !issues.contains(&issue) {
+                    issues.push(issue);
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Compare all translations of a language with the values of their keys
+    /// in the source language, and list those with issues.
+    pub fn lint_translations(&mut self, language_id: &str, user: Option<&User>) -> Result<Vec<TranslationLint>> {
+        let lang = self.must_get_language(language_id)?;
+        self.authorize_project(&lang.project_id, user, Permission::Read)?;
+
+        let settings = self.db()?.project_settings(&lang.project_id)?;
+        let source_id = match source_language_id(&settings, &lang) {
+            Some(id) => id,
+            None => {
+                return Ok(Vec::new());
+            },
+        };
+        let mut sources: BTreeMap<String, String> = self.db()?.translations_with_keys(&source_id)?
+            .into_iter()
+            .filter(|&(ref t, _)| t.value != "")
+            .map(|(t, k)| (k.id, t.value))
+            .collect();
+
+        let mut lints = Vec::new();
+        for (t, k) in self.db()?.translations_with_keys(&lang.id)? {
+            if t.value == "" {
+                continue;
+            }
+            let source = match sources.remove(&k.id) {
+                Some(s) => s,
+                None => continue,
+            };
+            let issues = ::lint::lint(settings.syntax(), &source, &t.value);
+            if !issues.is_empty() {
+                lints.push(TranslationLint {
+                    key: k,
+                    translation: t,
+                    source,
+                    issues,
+                });
+            }
+        }
+        lints.sort_by(|a, b| a.key.key.cmp(&b.key.key));
+        Ok(lints)
+    }
+
+    /// Import translations for a language within a single transaction.
+    /// Missing keys are created if the user may manage keys.
+    pub fn import_translations(&mut self, language_id: &str, format: ImportFormat, content: &str, mode: ImportMode, user: Option<&User>)
+        -> Result<ImportReport>
+    {
+        if mode == ImportMode::DryRun {
+            self.import_entries(language_id, format, content, mode, user)
         } else {
-            self.create_translation(translation, user.map(|u| u.id.clone()))
+            self.transaction(|repo| repo.import_entries(language_id, format, content, mode, user))
+        }
+    }
+
+    fn import_entries(&mut self, language_id: &str, format: ImportFormat, content: &str, mode: ImportMode, user: Option<&User>)
+        -> Result<ImportReport>
+    {
+        let lang = self.must_get_language(language_id)?;
+        let project_id = lang.project_id.clone();
+        self.authorize_project(&project_id, user, Permission::Translate)?;
+        let mut can_create_keys = self.authorize_project(&project_id, user, Permission::ManageKeys).is_ok();
+
+        // Current source texts and the source texts the entries were
+        // translated from, by key.
+        let mut sources: Option<BTreeMap<String, String>> = None;
+        let mut doc_sources: BTreeMap<String, String> = BTreeMap::new();
+
+        let mut keys: BTreeMap<String, Key> = self.db()?.keys(&project_id)?
+            .into_iter()
+            .map(|k| (k.key.clone(), k))
+            .collect();
+
+        let entries = match format {
+            ImportFormat::Json => group_plurals(::formats::json::parse_translations(content)?, &keys),
+            ImportFormat::Po => ::formats::po::parse_catalog(content, &lang.code)?,
+            ImportFormat::Xliff => {
+                let doc = ::formats::xliff::parse_document(content)?;
+                if let Some(ref code) = doc.target_language {
+                    if code != &lang.code {
+                        return Err(format!("XLIFF target language {} does not match {}", code, lang.code).into());
+                    }
+                }
+
+                // Units reference existing keys.
+                can_create_keys = false;
+                let source_lang = match doc.source_language {
+                    Some(ref code) => self.db()?.languages(&project_id)?.into_iter().find(|l| &l.code == code),
+                    None => None,
+                };
+                if let Some(source_lang) = source_lang {
+                    sources = Some(self.db()?.translations_with_keys(&source_lang.id)?
+                        .into_iter()
+                        .map(|(t, k)| (k.key, t.value))
+                        .collect());
+                }
+
+                let mut entries = TranslationsImport::new();
+                for unit in doc.units {
+                    // Units without a target were not translated.
+                    match unit.target {
+                        Some(target) if target != "" => {
+                            doc_sources.insert(unit.id.clone(), unit.source);
+                            entries.insert(unit.id, ImportValue::Single(target));
+                        },
+                        _ => {},
+                    }
+                }
+                entries
+            },
+        };
+
+        // Current plural forms by translation.
+        let mut forms: BTreeMap<String, Vec<PluralForm>> = BTreeMap::new();
+        for f in self.db()?.plural_forms_by_lang(&lang.id)? {
+            forms.entry(f.translation_id.clone()).or_insert(Vec::new()).push(f);
+        }
+        let mut current: BTreeMap<String, Translation> = self.db()?.translations_with_keys(&lang.id)?
+            .into_iter()
+            .map(|(t, k)| (k.key, t))
+            .collect();
+
+        let dry_run = mode == ImportMode::DryRun;
+        let mut report = ImportReport::new(dry_run);
+
+        for (name, value) in entries {
+            if !keys.contains_key(&name) {
+                if !can_create_keys {
+                    report.unknown_keys.push(name);
+                    continue;
+                }
+                if let Err(e) = self.validate_key(&project_id, &name) {
+                    report.error(&name, e.to_string());
+                    continue;
+                }
+                // Dry runs don't create keys, so check them against the other
+                // new keys as well.
+                let nested = report.created_keys.iter().find(|k| keys_nested(k, &name)).cloned();
+                if let Some(other) = nested {
+                    report.error(&name, format!("Invalid nested key: conflicts with the new key {}", other));
+                    continue;
+                }
+
+                report.created_keys.push(name.clone());
+                if !dry_run {
+                    let plural = match value {
+                        ImportValue::Plural(_) => Some(true),
+                        ImportValue::Single(_) => None,
+                    };
+                    let key = self.create_key(NewKey {
+                        project_id: Some(project_id.clone()),
+                        key: name.clone(),
+                        description: None,
+                        plural,
+                    }, user)?;
+                    keys.insert(name.clone(), key);
+                }
+            }
+
+            if let Some(ref sources) = sources {
+                // Empty sources were not exported.
+                let source = sources.get(&name).map(|s| s.as_str()).unwrap_or("");
+                if doc_sources.get(&name).map(|s| s.as_str()) != Some(source) {
+                    report.conflicts.push(ImportError {
+                        key: name,
+                        message: "The source text changed since the file was exported".to_string(),
+                    });
+                    continue;
+                }
+            }
+
+            let value = match value {
+                ImportValue::Plural(mut values) => {
+                    let existing = current.get(&name).and_then(|t| forms.get(&t.id));
+                    let filled = fill_fraction_forms(&lang.code, &mut values, existing);
+                    if !filled.is_empty() {
+                        report.warnings.push(ImportError {
+                            key: name.clone(),
+                            message: format!("The {} forms for fractions were copied from the last plural form",
+                                             filled.join(", ")),
+                        });
+                    }
+                    ImportValue::Plural(values)
+                },
+                v => v,
+            };
+
+            match current.remove(&name) {
+                Some(ref t) if import_unchanged(t, &value, &forms) => {
+                    report.skipped.push(name);
+                    continue;
+                },
+                Some(_) if mode == ImportMode::AddMissing => {
+                    report.skipped.push(name);
+                    continue;
+                },
+                Some(_) => report.updated.push(name.clone()),
+                None => report.added.push(name.clone()),
+            }
+
+            if dry_run {
+                continue;
+            }
+
+            let key_id = keys[&name].id.parse::<Uuid>().chain_err(|| "Invalid key id")?;
+            let (value, plural_values) = match value {
+                ImportValue::Single(v) => (v, None),
+                ImportValue::Plural(values) => (String::new(), Some(values)),
+            };
+            let translation = NewTranslation {
+                language_id: lang.id.parse::<Uuid>().chain_err(|| "Invalid language id")?,
+                key_id,
+                value,
+                plural_values,
+                expected_version: None,
+                draft: None,
+            };
+            match self.translate(translation, user) {
+                Ok(t) => {
+                    let issues = self.lint_translation(&t)?;
+                    if !issues.is_empty() {
+                        report.warnings.push(ImportError {
+                            key: name,
+                            message: issues.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", "),
+                        });
+                    }
+                },
+                Err(e) => {
+                    // Don't report the key as changed.
+                    report.added.retain(|k| k != &name);
+                    report.updated.retain(|k| k != &name);
+                    report.error(&name, e.to_string());
+                },
+            }
         }
+
+        Ok(report)
     }
 
-    pub fn create_translation(&mut self, translation: NewTranslation, user_id: Option<String>)
+    pub fn create_translation(&mut self, translation: NewTranslation, source_version: Option<i32>, user_id: Option<String>)
         -> Result<Translation>
     {
         let now = Utc::now().timestamp();
@@ -359,32 +2000,293 @@ impl Repo {
             id: Uuid::new_v4().to_string(),
             language_id: translation.language_id.to_string(),
             key_id: translation.key_id.to_string(),
+            status: submitted_status(&translation).to_str().to_string(),
             value: translation.value,
             created_at: now,
             updated_at: now,
             created_by: user_id,
             version: 1,
+            review_comment: None,
+            reviewed_at: None,
+            reviewed_by: None,
+            source_version,
+            outdated: false,
+            approved_version: None,
         };
         self.db()?.create_translation(translation)
     }
 
-    pub fn update_translation(&mut self, translation: NewTranslation, user: Option<&User>)
-        -> Result<Translation>
-    {
-        unimplemented!();
-    }
-
     pub fn delete_translation(&mut self, id: &str, user: Option<&User>) -> Result<()> {
+        let translation = self.must_get_translation(id)?;
+        let key = self.must_get_key(&translation.key_id)?;
+        self.authorize_project(&key.project_id, user, Permission::Translate)?;
         self.db()?.delete_translation(id)?;
         Ok(())
     }
 
 
     pub fn users(&mut self, user: Option<&User>) -> Result<Vec<User>> {
+        self.authorize(user, Permission::ManageUsers)?;
         self.db()?.users()
     }
 
-    pub fn export(&mut self) -> Result<db::Export> {
-        self.db()?.export()
+    fn must_get_user(&mut self, id: &str) -> Result<User> {
+        match self.db()?.user_by_id(id)? {
+            Some(u) => Ok(u),
+            None => Err(ErrorKind::UnknownUser.into()),
+        }
+    }
+
+    pub fn create_user(&mut self, username: String, role: &str, password: String, user: Option<&User>)
+        -> Result<User>
+    {
+        self.authorize(user, Permission::ManageUsers)?;
+        let role = Role::from_str(role)?;
+
+        let username = username.trim().to_string();
+        if username == "" {
+            return Err("Username may not be empty".into());
+        }
+        if password.trim() == "" {
+            return Err(ErrorKind::InvalidPassword.into());
+        }
+
+        let db = self.db()?;
+        if db.user_by_username(&username)?.is_some() {
+            return Err("Username already taken".into());
+        }
+        db.create_user(username, role, password)
+    }
+
+    pub fn update_user(&mut self, id: &str, role: Option<String>, password: Option<String>, user: Option<&User>)
+        -> Result<User>
+    {
+        self.authorize(user, Permission::ManageUsers)?;
+
+        let mut target = self.must_get_user(id)?;
+        if let Some(role) = role {
+            target.role = Role::from_str(&role)?.to_str().to_string();
+        }
+        if let Some(password) = password {
+            if password.trim() == "" {
+                return Err(ErrorKind::InvalidPassword.into());
+            }
+            target.set_password(password);
+        }
+
+        self.db()?.update_user(&target)?;
+        Ok(target)
+    }
+
+    pub fn delete_user(&mut self, id: &str, user: Option<&User>) -> Result<()> {
+        self.authorize(user, Permission::ManageUsers)?;
+        if user.map(|u| u.id == id).unwrap_or(false) {
+            return Err("You can not delete your own account".into());
+        }
+
+        self.must_get_user(id)?;
+        self.db()?.delete_user(id)
+    }
+
+    /// Change the password of the current user.
+    pub fn change_password(&mut self, old_password: &str, new_password: &str, user: Option<&User>)
+        -> Result<()>
+    {
+        let id = match user {
+            Some(u) => u.id.clone(),
+            None => {
+                return Err(ErrorKind::PermissionDenied.into());
+            },
+        };
+
+        let mut target = self.must_get_user(&id)?;
+        if !target.verify_password(old_password) {
+            return Err(ErrorKind::InvalidPassword.into());
+        }
+        if new_password.trim() == "" {
+            return Err(ErrorKind::InvalidPassword.into());
+        }
+
+        target.set_password(new_password);
+        self.db()?.update_user(&target)
+    }
+
+    /// Export the full database.
+    /// Password hashes are only included if explicitly requested, for backups.
+    pub fn export(&mut self, include_password_hashes: bool, user: Option<&User>) -> Result<db::Export> {
+        self.authorize(user, Permission::ManageUsers)?;
+        let mut export = self.db()?.export()?;
+        if !include_password_hashes {
+            for u in export.users.iter_mut() {
+                u.password_hash.clear();
+            }
+        }
+        Ok(export)
+    }
+
+    /// Restore a full export, as produced by `export`.
+    /// New users are only created without a password if `force` is set.
+    pub fn restore(&mut self, content: &str, force: bool, user: Option<&User>) -> Result<db::RestoreReport> {
+        self.authorize(user, Permission::ManageUsers)?;
+        let export = db::parse_export(content)?;
+        self.db()?.restore(export, force)
+    }
+}
+
+/// Whether one of two keys is nested within the other.
+fn keys_nested(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    long.starts_with(short) && long[short.len()..].starts_with('.')
+}
+
+/// The language translations are made from, which is the source language of
+/// the project, or else its default language.
+fn project_source_language(settings: &ProjectSettings) -> Option<&String> {
+    settings.source_language_id.as_ref().or(settings.default_language_id.as_ref())
+}
+
+/// The language translations of `lang` are made from. None for the source
+/// language itself.
+fn source_language_id(settings: &ProjectSettings, lang: &Language) -> Option<String> {
+    match project_source_language(settings) {
+        Some(id) if id != &lang.id => Some(id.clone()),
+        _ => None,
+    }
+}
+
+/// The path of the `.stringsdict` next to an iOS `.strings` file.
+fn stringsdict_path(path: &str) -> String {
+    if path.ends_with(".strings") {
+        format!("{}dict", path)
+    } else {
+        format!("{}.stringsdict", path)
+    }
+}
+
+/// Whether importing the value leaves the translation unchanged.
+fn import_unchanged(t: &Translation, value: &ImportValue, forms: &BTreeMap<String, Vec<PluralForm>>) -> bool {
+    match *value {
+        ImportValue::Single(ref v) => &t.value == v,
+        ImportValue::Plural(ref values) => {
+            let current = forms.get(&t.id).map(|f| f.as_slice()).unwrap_or(&[]);
+            current.len() == values.len() &&
+                values.iter().all(|v| current.iter().any(|f| f.category == v.category && f.value == v.value))
+        },
+    }
+}
+
+/// Fill the plural categories of a language that only cover fractions, which
+/// gettext catalogs have no forms for. They keep their current values, or else
+/// take the value of the last integer form. Returns the categories copied from
+/// that form.
+fn fill_fraction_forms(code: &str, values: &mut Vec<PluralValue>, current: Option<&Vec<PluralForm>>) -> Vec<&'static str> {
+    let rule = ::plurals::rule(code);
+    let mut copied = Vec::new();
+    for category in rule.categories {
+        if rule.gettext_categories.contains(category) || values.iter().any(|v| v.category == *category) {
+            continue;
+        }
+        let value = match current.and_then(|forms| forms.iter().find(|f| f.category == *category)) {
+            Some(form) => form.value.clone(),
+            None => {
+                let last = rule.gettext_categories.iter().rev()
+                    .filter_map(|c| values.iter().find(|v| v.category == *c))
+                    .next()
+                    .map(|v| v.value.clone());
+                match last {
+                    Some(value) => {
+                        copied.push(*category);
+                        value
+                    },
+                    None => continue,
+                }
+            },
+        };
+        values.push(PluralValue { category: category.to_string(), value });
+    }
+    copied
+}
+
+/// Group the values of plural keys by key. Formats like json list them as
+/// `key.category` entries.
+fn group_plurals(entries: TranslationsExport, keys: &BTreeMap<String, Key>) -> TranslationsImport {
+    let mut out = TranslationsImport::new();
+    for (name, value) in entries {
+        let split = match name.rfind('.') {
+            Some(i) if keys.get(&name[..i]).map(|k| k.plural).unwrap_or(false) => {
+                ::plurals::category(&name[i + 1..]).map(|c| (name[..i].to_string(), c))
+            },
+            _ => None,
+        };
+        match split {
+            Some((key, category)) => {
+                let entry = out.entry(key).or_insert(ImportValue::Plural(Vec::new()));
+                if let ImportValue::Plural(ref mut values) = *entry {
+                    values.push(PluralValue {
+                        category: category.to_string(),
+                        value,
+                    });
+                }
+            },
+            None => {
+                out.insert(name, ImportValue::Single(value));
+            },
+        }
     }
-}
\ No newline at end of file
+    out
+}
+
+/// The values of a plural translation by category, in canonical order.
+/// Every category of the language needs a value, so translations of plural
+/// keys without plural values, which set the other form, are only valid in
+/// languages without further categories. Empty for other keys.
+fn plural_values(key: &Key, lang: &Language, translation: &NewTranslation) -> Result<Vec<(&'static str, String)>> {
+    let values = match translation.plural_values {
+        Some(_) if !key.plural => {
+            return Err(format!("Key {} is not a plural", key.key).into());
+        },
+        Some(ref values) => values.clone(),
+        None if key.plural => vec![PluralValue {
+            category: "other".to_string(),
+            value: translation.value.clone(),
+        }],
+        None => {
+            return Ok(Vec::new());
+        },
+    };
+
+    let rule = ::plurals::rule(&lang.code);
+    let mut forms: Vec<(&'static str, String)> = Vec::new();
+    for v in values {
+        let category = match ::plurals::category(&v.category) {
+            Some(c) if rule.categories.contains(&c) => c,
+            _ => {
+                return Err(format!("Language {} has no plural category {}", lang.code, v.category).into());
+            },
+        };
+        if forms.iter().any(|f| f.0 == category) {
+            return Err(format!("Duplicate value for the plural category {}", category).into());
+        }
+        forms.push((category, v.value));
+    }
+
+    let missing: Vec<&str> = rule.categories.iter()
+        .map(|c| *c)
+        .filter(|c| !forms.iter().any(|f| f.0 == *c && f.1 != ""))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing plural values for {}", missing.join(", ")).into());
+    }
+
+    forms.sort_by_key(|f| rule.categories.iter().position(|c| *c == f.0));
+    Ok(forms)
+}
+
+/// The status of a newly saved value.
+fn submitted_status(translation: &NewTranslation) -> TranslationStatus {
+    if translation.draft.unwrap_or(false) {
+        TranslationStatus::Draft
+    } else {
+        TranslationStatus::NeedsReview
+    }
+}
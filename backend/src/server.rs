@@ -1,20 +1,22 @@
 use std::io::Cursor;
 
-use rocket::{self, State, Request, Response};
+use rocket::{self, State, Request, Response, Outcome};
 use rocket::fairing::{Fairing, Info, Kind};
-use rocket::http::{Header, ContentType, Method};
+use rocket::http::{Header, ContentType, Method, Status};
+use rocket::request::{self, FromRequest};
 use rocket_contrib::Json;
-use rocket::response::Content;
+use rocket::response::{self, Content, Responder, status};
 use rocket::response::content;
-use serde_json::{self};
+use serde_json::{self, Value};
 use juniper_rocket;
 
 use ::error::*;
-use ::db::{self, Db, BaseData, TranslationData};
+use ::db::{self, Db, BaseData, TranslationData, User, ApiToken};
 use ::commands::{Ctx};
+use ::formats::xliff::XliffVersion;
 use ::api::{self, Schema};
 use ::app::App;
-use ::repo::{ExportFormat};
+use ::repo::{Repo, ExportFormat, ExportOptions, ImportFormat, ImportMode, ImportReport};
 
 pub struct CORS;
 
@@ -57,6 +59,104 @@ pub enum CommandResult {
     },
 }
 
+/// Request guard that authenticates the user from a `Bearer` token in the
+/// `Authorization` header.
+/// Requests without the header are treated as anonymous.
+pub struct Auth {
+    user: Option<User>,
+    token: Option<ApiToken>,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for Auth {
+    type Error = Error;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Error> {
+        let header = match request.headers().get_one("Authorization") {
+            Some(h) => h.trim(),
+            None => {
+                return Outcome::Success(Auth { user: None, token: None });
+            },
+        };
+
+        if !header.starts_with("Bearer ") {
+            return Outcome::Failure((Status::Unauthorized, ErrorKind::InvalidToken.into()));
+        }
+        let token = header["Bearer ".len()..].trim();
+
+        let app = match request.guard::<State<App>>() {
+            Outcome::Success(app) => app,
+            _ => {
+                return Outcome::Failure((Status::InternalServerError, "App state missing".into()));
+            },
+        };
+
+        match app.repo().authenticate(token) {
+            Ok((user, token)) => Outcome::Success(Auth { user: Some(user), token: Some(token) }),
+            Err(e) => Outcome::Failure((Status::Unauthorized, e)),
+        }
+    }
+}
+
+impl<'r> Responder<'r> for Error {
+    fn respond_to(self, _: &Request) -> response::Result<'r> {
+        let status = match *self.kind() {
+            // Plain message errors are validation failures.
+            ErrorKind::Msg(_) => Status::BadRequest,
+            ErrorKind::IncompatibleExport(_) => Status::BadRequest,
+            ErrorKind::InvalidMessage(_) => Status::BadRequest,
+            ErrorKind::InvalidRole | ErrorKind::InvalidScope(_) => Status::BadRequest,
+            // Malformed json in imported files.
+            ErrorKind::Json(_) => Status::BadRequest,
+            ErrorKind::TranslationConflict(..) => Status::Conflict,
+            ErrorKind::PermissionDenied => Status::Forbidden,
+            ErrorKind::UnknownProject | ErrorKind::UnknownLanguage | ErrorKind::UnknownKey => Status::NotFound,
+            ErrorKind::UnknownUser | ErrorKind::UnknownTranslation => Status::NotFound,
+            ErrorKind::InvalidToken | ErrorKind::TokenExpired => Status::Unauthorized,
+            ErrorKind::InvalidPassword => Status::Unauthorized,
+            _ => {
+                eprintln!("Request failed: {}", self);
+                return Err(Status::InternalServerError);
+            },
+        };
+
+        Response::build()
+            .status(status)
+            .header(ContentType::Plain)
+            .sized_body(Cursor::new(self.to_string()))
+            .ok()
+    }
+}
+
+impl Auth {
+    /// Authenticate with an explicitly passed token, e.g. from a `token` query argument,
+    /// falling back to the `Authorization` header.
+    fn or_token(self, app: &App, token: Option<String>) -> Result<Auth> {
+        match token {
+            Some(token) => {
+                let (user, token) = app.repo().authenticate(&token)?;
+                Ok(Auth { user: Some(user), token: Some(token) })
+            },
+            None => Ok(self),
+        }
+    }
+
+    fn repo(&self, app: &App) -> Repo {
+        app.repo().with_token(self.token.as_ref())
+    }
+}
+
+type GraphQLResult = ::std::result::Result<juniper_rocket::GraphQLResponse, status::Custom<Json<Value>>>;
+
+/// Render an authentication failure in the GraphQL error format.
+fn graphql_auth_error(err: Error) -> status::Custom<Json<Value>> {
+    let data = json!({
+        "errors": [
+            { "message": err.to_string() },
+        ],
+    });
+    status::Custom(Status::Unauthorized, Json(data))
+}
+
 #[get("/")]
 fn index() -> Content<&'static str> {
     let index_file = include_bytes!("../../frontend/dist/index.html");
@@ -73,41 +173,176 @@ fn assets_js() -> Content<&'static str> {
     Content(ContentType::JavaScript, content)
 }
 
+fn export_content_type(format: ExportFormat) -> ContentType {
+    match format {
+        ExportFormat::Json => ContentType::JSON,
+        ExportFormat::Javascript | ExportFormat::Typescript => ContentType::JavaScript,
+        ExportFormat::Po | ExportFormat::Pot | ExportFormat::Ios | ExportFormat::Rust => ContentType::Plain,
+        ExportFormat::Android | ExportFormat::Stringsdict => ContentType::XML,
+    }
+}
+
 #[derive(FromForm)]
 struct ExportArgs {
     format: Option<String>,
     pretty: Option<bool>,
+    fallback: Option<bool>,
+    annotate: Option<bool>,
+    nested: Option<bool>,
+    only_approved: Option<bool>,
     token: Option<String>,
 }
 
-#[get("/export/translations/<lang>?<args>")]
-fn export_translations(lang: String, args: ExportArgs, app: State<App>) -> Result<Content<String>> {
+#[get("/export/<project>/translations/<lang>?<args>")]
+fn export_translations(project: String, lang: String, args: ExportArgs, app: State<App>, auth: Auth) -> Result<Content<String>> {
+    let auth = auth.or_token(&app, args.token)?;
     let format = args.format
                      .and_then(|x| ExportFormat::from_str(&x))
                      .unwrap_or(ExportFormat::Json);
-    let pretty = args.pretty.unwrap_or(false);
-
+    let options = ExportOptions {
+        pretty: args.pretty.unwrap_or(false),
+        fallback: args.fallback.unwrap_or(false),
+        annotate: args.annotate.unwrap_or(false),
+        nested: args.nested.unwrap_or(false),
+        only_approved: args.only_approved.unwrap_or(false),
+    };
 
-    let export = app.repo().translations_export(lang, format, pretty)?;
-    Ok(Content(ContentType::JSON, export))
+    let export = auth.repo(&app).translations_export(&project, &lang, format, options, auth.user.as_ref())?;
+    Ok(Content(export_content_type(format), export))
 }
 
-#[get("/export/keys?<args>")]
-fn export_keys(args: ExportArgs, app: State<App>) -> Result<Content<String>> {
+#[get("/export/<project>/keys?<args>")]
+fn export_keys(project: String, args: ExportArgs, app: State<App>, auth: Auth) -> Result<Content<String>> {
+    let auth = auth.or_token(&app, args.token)?;
     let format = args.format.and_then(|x| ExportFormat::from_str(&x)).unwrap_or(ExportFormat::Json);
-    let pretty = args.pretty.unwrap_or(false);
+    let options = ExportOptions {
+        pretty: args.pretty.unwrap_or(false),
+        only_approved: args.only_approved.unwrap_or(false),
+        ..ExportOptions::default()
+    };
+
+    let export = auth.repo(&app).keys_export(&project, format, options, auth.user.as_ref())?;
+    Ok(Content(export_content_type(format), export))
+}
+
+#[derive(FromForm)]
+struct BundleArgs {
+    format: Option<String>,
+    pretty: Option<bool>,
+    fallback: Option<bool>,
+    nested: Option<bool>,
+    only_approved: Option<bool>,
+    /// Path template for the translation files.
+    path: Option<String>,
+    /// Path of the keys file.
+    keys_path: Option<String>,
+    token: Option<String>,
+}
+
+#[get("/export/<project>/bundle?<args>")]
+fn export_bundle(project: String, args: BundleArgs, app: State<App>, auth: Auth) -> Result<Content<Vec<u8>>> {
+    let auth = auth.or_token(&app, args.token)?;
+    let format = args.format
+                     .map(|x| ExportFormat::from_str(&x).ok_or("Unsupported export format"))
+                     .unwrap_or(Ok(ExportFormat::Json))?;
+    let options = ExportOptions {
+        pretty: args.pretty.unwrap_or(false),
+        fallback: args.fallback.unwrap_or(false),
+        annotate: false,
+        nested: args.nested.unwrap_or(false),
+        only_approved: args.only_approved.unwrap_or(false),
+    };
 
-    let export = app.repo().keys_export(format, pretty)?;
-    Ok(Content(ContentType::JSON, export))
+    let bundle = auth.repo(&app).bundle_export(&project, format, options, args.path, args.keys_path, auth.user.as_ref())?;
+    Ok(Content(ContentType::new("application", "zip"), bundle))
+}
+
+#[derive(FromForm)]
+struct XliffArgs {
+    version: Option<String>,
+    token: Option<String>,
+}
+
+#[get("/export/<project>/xliff/<source>/<target>?<args>")]
+fn export_xliff(project: String, source: String, target: String, args: XliffArgs, app: State<App>, auth: Auth)
+    -> Result<Content<String>>
+{
+    let auth = auth.or_token(&app, args.token)?;
+    let version = args.version
+                      .and_then(|x| XliffVersion::from_str(&x))
+                      .unwrap_or(XliffVersion::V1_2);
+
+    let export = auth.repo(&app).xliff_export(&project, &source, &target, version, auth.user.as_ref())?;
+    Ok(Content(ContentType::XML, export))
+}
+
+#[derive(FromForm)]
+struct BackupArgs {
+    password_hashes: Option<bool>,
+    token: Option<String>,
 }
 
+#[get("/export/all?<args>")]
+fn export_all(args: BackupArgs, app: State<App>, auth: Auth) -> Result<Json<db::Export>> {
+    let auth = auth.or_token(&app, args.token)?;
+    let include_hashes = args.password_hashes.unwrap_or(false);
+    let export = auth.repo(&app).export(include_hashes, auth.user.as_ref())?;
+    Ok(Json(export))
+}
 
-#[get("/export/all")]
-fn export_all(app: State<App>) -> Result<Json<db::Export>> {
-    let export = app.repo().export()?;
+// Query routes only match when a query string is present.
+#[get("/export/all", rank = 2)]
+fn export_all_default(app: State<App>, auth: Auth) -> Result<Json<db::Export>> {
+    let export = auth.repo(&app).export(false, auth.user.as_ref())?;
     Ok(Json(export))
 }
 
+#[derive(FromForm)]
+struct ImportArgs {
+    format: Option<String>,
+    mode: Option<String>,
+    token: Option<String>,
+}
+
+#[post("/import/<project>/translations/<lang>?<args>", data = "<content>")]
+fn import_translations(project: String, lang: String, args: ImportArgs, content: String, app: State<App>, auth: Auth)
+    -> Result<Json<ImportReport>>
+{
+    let auth = auth.or_token(&app, args.token)?;
+    let format = args.format
+                     .map(|x| ImportFormat::from_str(&x).ok_or("Unsupported import format"))
+                     .unwrap_or(Ok(ImportFormat::Json))?;
+    let mode = args.mode
+                   .map(|x| ImportMode::from_str(&x).ok_or("Invalid import mode"))
+                   .unwrap_or(Ok(ImportMode::AddMissing))?;
+
+    let mut repo = auth.repo(&app);
+    let lang = repo.project_language(&project, &lang, auth.user.as_ref())?;
+    let report = repo.import_translations(&lang.id, format, &content, mode, auth.user.as_ref())?;
+    Ok(Json(report))
+}
+
+#[derive(FromForm)]
+struct RestoreArgs {
+    /// Create users even if the export has no password hashes.
+    force: Option<bool>,
+    token: Option<String>,
+}
+
+#[post("/import/all?<args>", data = "<content>")]
+fn import_all(args: RestoreArgs, content: String, app: State<App>, auth: Auth) -> Result<Json<db::RestoreReport>> {
+    let auth = auth.or_token(&app, args.token)?;
+    let force = args.force.unwrap_or(false);
+    let report = auth.repo(&app).restore(&content, force, auth.user.as_ref())?;
+    Ok(Json(report))
+}
+
+#[post("/import/all", data = "<content>", rank = 2)]
+fn import_all_default(content: String, app: State<App>, auth: Auth) -> Result<Json<db::RestoreReport>> {
+    let report = auth.repo(&app).restore(&content, false, auth.user.as_ref())?;
+    Ok(Json(report))
+}
+
 #[get("/api/graphiql")]
 fn graphiql() -> content::Html<String> {
     juniper_rocket::graphiql_source("/api/graphql")
@@ -124,9 +359,11 @@ fn get_graphql_handler(
     request: juniper_rocket::GraphQLRequest,
     schema: State<Schema>,
     app: State<App>,
-) -> juniper_rocket::GraphQLResponse {
-    let ctx = Ctx::new(app.clone(), None);
-    request.execute(&schema, &ctx)
+    auth: Result<Auth>,
+) -> GraphQLResult {
+    let auth = auth.map_err(graphql_auth_error)?;
+    let ctx = Ctx::new(app.clone(), auth.user, auth.token);
+    Ok(request.execute(&schema, &ctx))
 }
 
 #[post("/api/graphql", data="<request>")]
@@ -134,9 +371,11 @@ fn post_graphql_handler(
     request: juniper_rocket::GraphQLRequest,
     schema: State<Schema>,
     app: State<App>,
-) -> juniper_rocket::GraphQLResponse {
-    let ctx = Ctx::new(app.clone(), None);
-    request.execute(&schema, &ctx)
+    auth: Result<Auth>,
+) -> GraphQLResult {
+    let auth = auth.map_err(graphql_auth_error)?;
+    let ctx = Ctx::new(app.clone(), auth.user, auth.token);
+    Ok(request.execute(&schema, &ctx))
 }
 
 /*
@@ -202,7 +441,13 @@ pub fn run(app: ::app::App) {
             index,
             export_translations,
             export_keys,
+            export_xliff,
+            export_bundle,
             export_all,
+            export_all_default,
+            import_translations,
+            import_all,
+            import_all_default,
             assets_js,
             // Juniper graphql handlers.
             graphiql,
@@ -21,7 +21,7 @@ function launch() {
 
       if (token) {
         return {
-            headers: { authorization: 'Bearer ' + token},
+            headers: { authorization: 'Bearer ' + token.token},
         };
       } else {
         return {};